@@ -0,0 +1,359 @@
+//! Command line analysis of dagitty models, for batch jobs without a browser.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use why_data::graph::{
+    CausalGraph, NodeIndex,
+    dagitty::{EdgeInfo, NodeInfo, VertexType},
+};
+use why_parser::{dagitty::DagittyParser, dot::DotWriter, error::WarningKind, tikz::TikzWriter};
+
+type Model = CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>;
+
+/// Width and height of SVG pictures, the size of the graph in the browser.
+const SVG_SIZE: i32 = 764;
+
+/// Analyse causal models written in dagitty format.
+///
+/// Exits with status 1 when `lint` finds problems and 2 on errors. Nodes only
+/// used by edges are reported by `lint` without failing it.
+#[derive(Parser, Debug)]
+#[command(name = "why", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args, Debug)]
+struct Input {
+    /// Model file, standard input when missing or `-`.
+    file: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Minimal adjustment sets for the total effect of the exposures on the outcomes.
+    Adjust {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Whether two sets of nodes are d-separated.
+    Dsep {
+        /// Comma separated nodes.
+        x: String,
+        /// Comma separated nodes.
+        y: String,
+        /// Comma separated conditioning nodes.
+        #[arg(long, default_value = "")]
+        given: String,
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Minimal testable implications.
+    Implications {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Paths from the exposures to the outcomes given the adjusted nodes.
+    Paths {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Write the model in another format.
+    Convert {
+        /// Output format.
+        #[arg(long, value_enum)]
+        to: Format,
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Report likely mistakes in the model.
+    Lint {
+        #[command(flatten)]
+        input: Input,
+    },
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Format {
+    /// Graphviz DOT.
+    Dot,
+    /// JSON model schema.
+    Json,
+    /// Standalone SVG picture.
+    Svg,
+    /// TikZ picture.
+    Tikz,
+}
+
+impl Command {
+    fn input(&self) -> &Input {
+        match self {
+            Command::Adjust { input }
+            | Command::Dsep { input, .. }
+            | Command::Implications { input }
+            | Command::Paths { input }
+            | Command::Convert { input, .. }
+            | Command::Lint { input } => input,
+        }
+    }
+}
+
+/// Name of the input for messages, and its content.
+fn read_input(input: &Input) -> Result<(String, String), String> {
+    match &input.file {
+        Some(path) if path.as_os_str() != "-" => {
+            let name = path.display().to_string();
+            let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", name, e))?;
+            Ok((name, content))
+        }
+        _ => {
+            let mut content = String::new();
+            io::stdin()
+                .read_to_string(&mut content)
+                .map_err(|e| format!("<stdin>: {}", e))?;
+            Ok(("<stdin>".to_string(), content))
+        }
+    }
+}
+
+fn nodes(graph: &Model, ids: &str) -> Result<Vec<NodeIndex>, String> {
+    ids.split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            graph
+                .node_by_id(id)
+                .ok_or_else(|| format!("unknown node {}", id))
+        })
+        .collect()
+}
+
+/// Analyses of causal effects only hold for acyclic models.
+fn check_acyclic(graph: &Model) -> Result<(), String> {
+    match graph.find_cycle() {
+        Some(cycle) => Err(format!(
+            "the model has the cycle {}",
+            graph.cycle_to_string(&cycle)
+        )),
+        None => Ok(()),
+    }
+}
+
+fn adjust(graph: &Model) -> Result<String, String> {
+    check_acyclic(graph)?;
+    if graph.nodes_of_type(VertexType::Exposure).is_empty()
+        || graph.nodes_of_type(VertexType::Outcome).is_empty()
+    {
+        return Err("adjust needs an exposure and an outcome".to_string());
+    }
+    let sets = graph
+        .adjustment_sets()
+        .ok_or("adjustment sets need a dag")?;
+    if sets.canonical.is_none() {
+        return Err("the effect can't be identified by covariate adjustment".to_string());
+    }
+    let mut result = String::new();
+    for set in sets.minimal {
+        if set.is_empty() {
+            result.push_str("{}\n");
+        } else {
+            result.push_str(&format!("{{ {} }}\n", graph.nodes_to_string(&set)));
+        }
+    }
+    Ok(result)
+}
+
+fn dsep(graph: &Model, x: &str, y: &str, given: &str) -> Result<String, String> {
+    let (x, y, given) = (nodes(graph, x)?, nodes(graph, y)?, nodes(graph, given)?);
+    if x.is_empty() || y.is_empty() {
+        return Err("dsep needs nodes on both sides".to_string());
+    }
+    Ok(if graph.d_separated(&x, &y, &given) {
+        "d-separated\n".to_string()
+    } else {
+        "d-connected\n".to_string()
+    })
+}
+
+fn implications(graph: &Model) -> Result<String, String> {
+    check_acyclic(graph)?;
+    let implications = graph
+        .minimal_implications()
+        .ok_or("implications need a dag")?;
+    Ok(implications
+        .iter()
+        .map(|i| format!("{}\n", graph.independence_to_string(i)))
+        .collect())
+}
+
+fn paths(graph: &Model) -> Result<String, String> {
+    check_acyclic(graph)?;
+    let paths = graph.paths().ok_or("paths need a dag")?;
+    Ok(paths
+        .map(|path| {
+            format!(
+                "{} ({}, {})\n",
+                graph.path_to_string(&path),
+                if path.causal { "causal" } else { "non-causal" },
+                if path.open { "open" } else { "closed" }
+            )
+        })
+        .collect())
+}
+
+fn convert(graph: &Model, to: Format) -> Result<String, String> {
+    match to {
+        Format::Dot => Ok(format!("{}\n", DotWriter::write_str(graph))),
+        Format::Json => serde_json::to_string_pretty(graph)
+            .map(|json| format!("{}\n", json))
+            .map_err(|e| e.to_string()),
+        Format::Svg => Ok(graph.to_svg(SVG_SIZE, SVG_SIZE)),
+        Format::Tikz => Ok(TikzWriter::write_str(graph)),
+    }
+}
+
+/// Output of the command and whether lint found problems.
+fn execute(command: &Command, name: &str, content: &str) -> Result<(String, bool), String> {
+    let (graph, warnings) = DagittyParser::parse_str_with_warnings(content).map_err(|e| {
+        let mut messages: Vec<String> = e
+            .warnings
+            .iter()
+            .map(|w| format!("{}:{}", name, w))
+            .collect();
+        messages.push(format!("{}:{}", name, e));
+        messages.join("\n")
+    })?;
+    // Implicit nodes are valid dagitty, unlike the other warnings.
+    let problems = warnings
+        .iter()
+        .any(|w| w.kind != WarningKind::UndeclaredNode);
+    let warnings: Vec<String> = warnings
+        .iter()
+        .map(|w| format!("{}:{}\n", name, w))
+        .collect();
+
+    // Warnings explain errors of the command, so they come first.
+    if !matches!(command, Command::Lint { .. }) {
+        eprint!("{}", warnings.concat());
+    }
+    let output = match command {
+        Command::Lint { .. } => return Ok((warnings.concat(), problems)),
+        Command::Adjust { .. } => adjust(&graph),
+        Command::Dsep { x, y, given, .. } => dsep(&graph, x, y, given),
+        Command::Implications { .. } => implications(&graph),
+        Command::Paths { .. } => paths(&graph),
+        Command::Convert { to, .. } => convert(&graph, *to),
+    }?;
+    Ok((output, false))
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = read_input(cli.command.input())
+        .and_then(|(name, content)| execute(&cli.command, &name, &content));
+    match result {
+        Ok((output, problems)) => {
+            print!("{}", output);
+            if problems {
+                ExitCode::from(1)
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Err(message) => {
+            eprintln!("why: {}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = r#"dag {
+        E [exposure,pos="0,0"]
+        Y [outcome,pos="2,0"]
+        Z [pos="1,-1"]
+        M [pos="1,0"]
+        Z -> E
+        Z -> Y
+        E -> M -> Y
+    }"#;
+
+    fn run(args: &[&str], content: &str) -> Result<(String, bool), String> {
+        let cli = Cli::try_parse_from(args).map_err(|e| e.to_string())?;
+        execute(&cli.command, "model", content)
+    }
+
+    #[test]
+    fn test_analysis_commands() {
+        assert_eq!(run(&["why", "adjust"], MODEL).unwrap().0, "{ Z }\n");
+        assert_eq!(
+            run(&["why", "dsep", "E", "Y", "--given", "Z,M"], MODEL)
+                .unwrap()
+                .0,
+            "d-separated\n"
+        );
+        assert_eq!(
+            run(&["why", "dsep", "E", "Y"], MODEL).unwrap().0,
+            "d-connected\n"
+        );
+        assert_eq!(
+            run(&["why", "implications"], MODEL).unwrap().0,
+            "E _||_ Y | Z, M\nZ _||_ M | E\n"
+        );
+        assert_eq!(
+            run(&["why", "paths", "-"], MODEL).unwrap().0,
+            "E <- Z -> Y (non-causal, open)\nE -> M -> Y (causal, open)\n"
+        );
+        assert_eq!(
+            run(&["why", "dsep", "E", "W"], MODEL),
+            Err("unknown node W".to_string())
+        );
+        assert_eq!(
+            run(&["why", "adjust"], "dag { A -> B }"),
+            Err("adjust needs an exposure and an outcome".to_string())
+        );
+        let cyclic = "dag { A [exposure] B [outcome] A -> B B -> A }";
+        for command in ["adjust", "implications", "paths"] {
+            assert_eq!(
+                run(&["why", command], cyclic),
+                Err("the model has the cycle A -> B -> A".to_string())
+            );
+        }
+        for (x, y) in [("", "Y"), (",", "Y"), ("E", " ")] {
+            assert_eq!(
+                run(&["why", "dsep", x, y], MODEL),
+                Err("dsep needs nodes on both sides".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_convert_and_lint() {
+        let json = run(&["why", "convert", "--to", "json"], MODEL).unwrap().0;
+        assert!(json.contains(r#""kind": "dag""#));
+        let tikz = run(&["why", "convert", "--to", "tikz"], MODEL).unwrap().0;
+        assert!(tikz.contains(r"\node[exposure] (n0) at (0,0) {E};"));
+        let svg = run(&["why", "convert", "--to", "svg"], MODEL).unwrap().0;
+        assert!(svg.starts_with("<svg"));
+
+        assert_eq!(run(&["why", "lint"], MODEL), Ok((String::new(), false)));
+        let (output, problems) = run(&["why", "lint"], "dag { A -> B -> A }").unwrap();
+        assert!(problems);
+        assert!(output.starts_with("model:1:"));
+        let (output, problems) = run(&["why", "lint"], "dag { X -> Y }").unwrap();
+        assert!(!problems);
+        assert!(output.contains("undeclared node X"));
+
+        let error = run(&["why", "adjust"], "dag { A -> }").unwrap_err();
+        assert!(error.starts_with("model:1:"));
+    }
+}
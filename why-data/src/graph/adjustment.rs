@@ -0,0 +1,420 @@
+use std::collections::HashSet;
+
+use petgraph::Direction::Outgoing;
+
+use super::paths::{descendant_set, CausalPath, Paths};
+use super::separation::{ancestral_set, minimal_separators, moral_graph, separated};
+use super::{
+    bidirected_neighbors, directed_neighbors, CausalEdge, DefaultIx, Directed, Incoming, IndexType,
+    NodeIndex, StableGraph,
+};
+
+/// Covariate adjustment sets for the total effect of some exposures on some outcomes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdjustmentSets<Ix = DefaultIx> {
+    /// Canonical adjustment set, `None` when the effect can't be identified by adjustment.
+    pub canonical: Option<Vec<NodeIndex<Ix>>>,
+    /// Minimal sufficient adjustment sets, sorted.
+    pub minimal: Vec<Vec<NodeIndex<Ix>>>,
+}
+
+/// Verdict about a given adjustment set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdjustmentCheck<Ix = DefaultIx> {
+    /// True if the set is a valid adjustment set for the total effect.
+    pub valid: bool,
+    /// Non-causal paths from the exposures to the outcomes left open by the set.
+    pub biasing_paths: Vec<CausalPath<Ix>>,
+    /// Adjusted nodes that are descendants of the exposures along a causal path.
+    pub descendants: Vec<NodeIndex<Ix>>,
+}
+
+/// Nodes that must never be adjusted for together with the proper back-door graph.
+struct BackDoor<Ix: IndexType> {
+    /// Descendants of proper causal path nodes, plus the exposures.
+    forbidden: HashSet<NodeIndex<Ix>>,
+    /// First edges of proper causal paths, removed in the proper back-door graph.
+    removed: HashSet<(NodeIndex<Ix>, NodeIndex<Ix>)>,
+}
+
+impl<Ix: IndexType> BackDoor<Ix> {
+    fn new<N, E: CausalEdge>(
+        g: &StableGraph<N, E, Directed, Ix>,
+        exposures: &HashSet<NodeIndex<Ix>>,
+        outcomes: &HashSet<NodeIndex<Ix>>,
+    ) -> Self {
+        // Descendants of the exposures ignoring edges into them.
+        let mut de_x = exposures.clone();
+        let mut pending: Vec<NodeIndex<Ix>> = exposures.iter().copied().collect();
+        while let Some(node) = pending.pop() {
+            for child in directed_neighbors(g, node, Outgoing) {
+                if !exposures.contains(&child) && de_x.insert(child) {
+                    pending.push(child);
+                }
+            }
+        }
+
+        // Ancestors of the outcomes ignoring edges out of the exposures.
+        let an_y = ancestral_set(outcomes, |n| {
+            directed_neighbors(g, n, Incoming)
+                .into_iter()
+                .filter(|p| !exposures.contains(p))
+                .collect()
+        });
+
+        let proper_causal: HashSet<NodeIndex<Ix>> = de_x
+            .intersection(&an_y)
+            .filter(|n| !exposures.contains(n))
+            .copied()
+            .collect();
+
+        let mut forbidden = descendant_set(g, &proper_causal);
+        forbidden.extend(exposures.iter().copied());
+
+        let removed = exposures
+            .iter()
+            .flat_map(|x| {
+                directed_neighbors(g, *x, Outgoing)
+                    .into_iter()
+                    .filter(|c| proper_causal.contains(c))
+                    .map(move |c| (*x, c))
+            })
+            .collect();
+
+        Self { forbidden, removed }
+    }
+
+    /// Parents of `node` in the proper back-door graph.
+    fn parents<N, E: CausalEdge>(
+        &self,
+        g: &StableGraph<N, E, Directed, Ix>,
+        node: NodeIndex<Ix>,
+    ) -> Vec<NodeIndex<Ix>> {
+        directed_neighbors(g, node, Incoming)
+            .into_iter()
+            .filter(|p| !self.removed.contains(&(*p, node)))
+            .collect()
+    }
+}
+
+/// Enumerate the adjustment sets for the total effect of `exposures` on `outcomes`.
+///
+/// Nodes in `latent` are never part of an adjustment set. Sets are computed with
+/// the generalised back-door criterion on the proper back-door graph, which also
+/// holds for graphs with bidirected edges.
+pub fn adjustment_sets<N, E: CausalEdge, Ix: IndexType>(
+    g: &StableGraph<N, E, Directed, Ix>,
+    exposures: &[NodeIndex<Ix>],
+    outcomes: &[NodeIndex<Ix>],
+    latent: &[NodeIndex<Ix>],
+) -> AdjustmentSets<Ix> {
+    let x: HashSet<NodeIndex<Ix>> = exposures.iter().copied().collect();
+    let y: HashSet<NodeIndex<Ix>> = outcomes.iter().copied().collect();
+    let none = AdjustmentSets {
+        canonical: None,
+        minimal: vec![],
+    };
+    if x.is_empty() || y.is_empty() || !x.is_disjoint(&y) {
+        return none;
+    }
+
+    let back_door = BackDoor::new(g, &x, &y);
+    let not_allowed = |n: &NodeIndex<Ix>| {
+        x.contains(n) || y.contains(n) || back_door.forbidden.contains(n) || latent.contains(n)
+    };
+
+    let xy: HashSet<NodeIndex<Ix>> = x.union(&y).copied().collect();
+    let mut canonical: Vec<NodeIndex<Ix>> =
+        ancestral_set(&xy, |n| directed_neighbors(g, n, Incoming))
+            .into_iter()
+            .filter(|n| !not_allowed(n))
+            .collect();
+    canonical.sort();
+
+    let mut xyz = xy.clone();
+    xyz.extend(canonical.iter().copied());
+    let moral = moral_graph(
+        &ancestral_set(&xyz, |n| back_door.parents(g, n)),
+        |n| back_door.parents(g, n),
+        |n| bidirected_neighbors(g, n),
+    );
+    if !separated(&moral, &x, &y, &canonical.iter().copied().collect()) {
+        return none;
+    }
+
+    let ancestors = ancestral_set(&xy, |n| back_door.parents(g, n));
+    let allowed = ancestors
+        .iter()
+        .filter(|n| !not_allowed(n))
+        .copied()
+        .collect();
+    let moral = moral_graph(
+        &ancestors,
+        |n| back_door.parents(g, n),
+        |n| bidirected_neighbors(g, n),
+    );
+
+    AdjustmentSets {
+        canonical: Some(canonical),
+        minimal: minimal_separators(&moral, &x, &y, &allowed),
+    }
+}
+
+/// Check whether `adjusted` is a valid adjustment set for the total effect of
+/// `exposures` on `outcomes`.
+pub fn check_adjustment_set<N, E: CausalEdge, Ix: IndexType>(
+    g: &StableGraph<N, E, Directed, Ix>,
+    exposures: &[NodeIndex<Ix>],
+    outcomes: &[NodeIndex<Ix>],
+    adjusted: &[NodeIndex<Ix>],
+) -> AdjustmentCheck<Ix> {
+    let x: HashSet<NodeIndex<Ix>> = exposures.iter().copied().collect();
+    let y: HashSet<NodeIndex<Ix>> = outcomes.iter().copied().collect();
+    let z: HashSet<NodeIndex<Ix>> = adjusted.iter().copied().collect();
+    let back_door = BackDoor::new(g, &x, &y);
+
+    let mut descendants: Vec<NodeIndex<Ix>> =
+        z.intersection(&back_door.forbidden).copied().collect();
+    descendants.sort();
+
+    let biasing_paths: Vec<CausalPath<Ix>> = Paths::new(g, exposures, outcomes, adjusted)
+        .filter(|path| !path.causal && path.open)
+        .collect();
+
+    let mut xyz = x.union(&y).copied().collect::<HashSet<NodeIndex<Ix>>>();
+    xyz.extend(z.iter().copied());
+    let moral = moral_graph(
+        &ancestral_set(&xyz, |n| back_door.parents(g, n)),
+        |n| back_door.parents(g, n),
+        |n| bidirected_neighbors(g, n),
+    );
+
+    AdjustmentCheck {
+        valid: !x.is_empty()
+            && !y.is_empty()
+            && descendants.is_empty()
+            && separated(&moral, &x, &y, &z),
+        biasing_paths,
+        descendants,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::dagitty::EdgeType as Edge;
+    use crate::graph::CausalGraphExt;
+
+    #[test]
+    fn test_m_bias() {
+        let mut g = StableGraph::<&str, Edge>::new();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let d = g.add_node("D");
+        let e = g.add_node("E");
+        let z = g.add_node("Z");
+        g.add_edge(a, e, Edge::Directed);
+        g.add_edge(a, z, Edge::Directed);
+        g.add_edge(b, d, Edge::Directed);
+        g.add_edge(b, z, Edge::Directed);
+        g.add_edge(e, d, Edge::Directed);
+
+        let sets = adjustment_sets(&g, &[e], &[d], &[]);
+        assert_eq!(Some(vec![a, b]), sets.canonical);
+        assert_eq!(vec![Vec::<NodeIndex>::new()], sets.minimal);
+    }
+
+    #[test]
+    fn test_confounder() {
+        let mut g = StableGraph::<&str, Edge>::new();
+        let c = g.add_node("C");
+        let x = g.add_node("X");
+        let m = g.add_node("M");
+        let w = g.add_node("W");
+        let y = g.add_node("Y");
+        g.add_edge(c, x, Edge::Directed);
+        g.add_edge(c, y, Edge::Directed);
+        g.add_edge(x, m, Edge::Directed);
+        g.add_edge(m, y, Edge::Directed);
+        g.add_edge(m, w, Edge::Directed);
+
+        let sets = adjustment_sets(&g, &[x], &[y], &[]);
+        assert_eq!(Some(vec![c]), sets.canonical);
+        assert_eq!(vec![vec![c]], sets.minimal);
+
+        let sets = adjustment_sets(&g, &[x], &[y], &[c]);
+        assert_eq!(None, sets.canonical);
+        assert!(sets.minimal.is_empty());
+    }
+
+    #[test]
+    fn test_latent_confounder_with_proxies() {
+        // X <- A <- U -> B -> Y, X -> Y with U unobserved.
+        let mut g = StableGraph::<&str, Edge>::new();
+        let u = g.add_node("U");
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let x = g.add_node("X");
+        let y = g.add_node("Y");
+        g.add_edge(u, a, Edge::Directed);
+        g.add_edge(u, b, Edge::Directed);
+        g.add_edge(a, x, Edge::Directed);
+        g.add_edge(b, y, Edge::Directed);
+        g.add_edge(x, y, Edge::Directed);
+
+        let sets = adjustment_sets(&g, &[x], &[y], &[u]);
+        assert_eq!(Some(vec![a, b]), sets.canonical);
+        assert_eq!(vec![vec![a], vec![b]], sets.minimal);
+    }
+
+    #[test]
+    fn test_check_adjustment_set() {
+        let mut g = StableGraph::<&str, Edge>::new();
+        let c = g.add_node("C");
+        let x = g.add_node("X");
+        let m = g.add_node("M");
+        let y = g.add_node("Y");
+        g.add_edge(c, x, Edge::Directed);
+        g.add_edge(c, y, Edge::Directed);
+        g.add_edge(x, m, Edge::Directed);
+        g.add_edge(m, y, Edge::Directed);
+
+        let check = check_adjustment_set(&g, &[x], &[y], &[c]);
+        assert!(check.valid);
+        assert!(check.biasing_paths.is_empty());
+        assert!(check.descendants.is_empty());
+
+        let check = check_adjustment_set(&g, &[x], &[y], &[m]);
+        assert!(!check.valid);
+        assert_eq!(1, check.biasing_paths.len());
+        assert_eq!(vec![x, c, y], check.biasing_paths[0].nodes);
+        assert_eq!(vec![m], check.descendants);
+    }
+
+    #[test]
+    fn test_check_adjustment_set_m_bias() {
+        let mut g = StableGraph::<&str, Edge>::new();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let d = g.add_node("D");
+        let e = g.add_node("E");
+        let z = g.add_node("Z");
+        g.add_edge(a, e, Edge::Directed);
+        g.add_edge(a, z, Edge::Directed);
+        g.add_edge(b, d, Edge::Directed);
+        g.add_edge(b, z, Edge::Directed);
+        g.add_edge(e, d, Edge::Directed);
+
+        let check = check_adjustment_set(&g, &[e], &[d], &[z]);
+        assert!(!check.valid);
+        assert_eq!(1, check.biasing_paths.len());
+        assert_eq!(vec![e, a, z, b, d], check.biasing_paths[0].nodes);
+
+        assert!(check_adjustment_set(&g, &[e], &[d], &[z, a]).valid);
+        assert!(check_adjustment_set(&g, &[e], &[d], &[]).valid);
+    }
+
+    #[test]
+    fn test_bidirected_confounder() {
+        // X <-> Y can't be adjusted for, X <-> C -> Y can.
+        let mut g = StableGraph::<&str, Edge>::new();
+        let c = g.add_node("C");
+        let x = g.add_node("X");
+        let y = g.add_node("Y");
+        g.add_edge(x, y, Edge::Directed);
+        g.add_edge(x, c, Edge::Bidirected);
+        g.add_edge(c, y, Edge::Directed);
+
+        let sets = adjustment_sets(&g, &[x], &[y], &[]);
+        assert_eq!(Some(vec![c]), sets.canonical);
+        assert_eq!(vec![vec![c]], sets.minimal);
+
+        let check = check_adjustment_set(&g, &[x], &[y], &[]);
+        assert!(!check.valid);
+        assert_eq!(vec![x, c, y], check.biasing_paths[0].nodes);
+        assert!(check_adjustment_set(&g, &[x], &[y], &[c]).valid);
+
+        g.add_edge(x, y, Edge::Bidirected);
+        let sets = adjustment_sets(&g, &[x], &[y], &[]);
+        assert_eq!(None, sets.canonical);
+        assert!(sets.minimal.is_empty());
+    }
+
+    /// Deterministic pseudo random generator for the exhaustive checks.
+    fn next(seed: &mut u64) -> u64 {
+        *seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        *seed >> 33
+    }
+
+    #[test]
+    fn test_minimal_sets_match_brute_force() {
+        let mut seed = 7;
+        for _ in 0..200 {
+            let mut g = StableGraph::<usize, Edge>::new();
+            let nodes: Vec<NodeIndex> = (0..7).map(|i| g.add_node(i)).collect();
+            for i in 0..nodes.len() {
+                for j in (i + 1)..nodes.len() {
+                    match next(&mut seed) % 9 {
+                        0..=2 => {
+                            g.add_edge(nodes[i], nodes[j], Edge::Directed);
+                        }
+                        3 => {
+                            g.add_edge(nodes[i], nodes[j], Edge::Bidirected);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            let x = nodes[(next(&mut seed) % 3) as usize];
+            let y = nodes[6];
+            let latent = vec![nodes[(next(&mut seed) % 7) as usize]];
+            if latent.contains(&x) {
+                continue;
+            }
+
+            let xs = HashSet::from([x]);
+            let back_door = BackDoor::new(&g, &xs, &HashSet::from([y]));
+            let mut pbd = g.clone();
+            pbd.retain_edges(|g, e| {
+                let (s, t) = g.edge_endpoints(e).unwrap();
+                g[e] != Edge::Directed || !back_door.removed.contains(&(s, t))
+            });
+            let candidates: Vec<NodeIndex> = nodes
+                .iter()
+                .filter(|n| {
+                    **n != x && **n != y && !latent.contains(n) && !back_door.forbidden.contains(n)
+                })
+                .copied()
+                .collect();
+
+            let mut valid: Vec<Vec<NodeIndex>> = vec![];
+            for mask in 0..(1usize << candidates.len()) {
+                let z: Vec<NodeIndex> = candidates
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| mask & (1 << i) != 0)
+                    .map(|(_, n)| *n)
+                    .collect();
+                if pbd.d_separated(&[x], &[y], &z) {
+                    valid.push(z);
+                }
+            }
+            let mut expected: Vec<Vec<NodeIndex>> = valid
+                .iter()
+                .filter(|z| {
+                    !valid
+                        .iter()
+                        .any(|w| w.len() < z.len() && w.iter().all(|n| z.contains(n)))
+                })
+                .cloned()
+                .collect();
+            expected.sort();
+
+            let sets = adjustment_sets(&g, &[x], &[y], &latent);
+            assert_eq!(expected, sets.minimal);
+            assert_eq!(expected.is_empty(), sets.canonical.is_none());
+        }
+    }
+}
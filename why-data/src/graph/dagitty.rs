@@ -1,11 +1,19 @@
+use crate::mutable::Mutable;
 use crate::types::Point;
-use futures_signals::signal::Mutable;
+use std::fmt::Display;
 use std::sync::Arc;
 
-use super::CausalGraph;
+use petgraph::visit::EdgeRef;
+
+use super::adjustment::{adjustment_sets, check_adjustment_set, AdjustmentCheck, AdjustmentSets};
+use super::implications::{basis_implications, minimal_implications, Independence};
+use super::paths::{CausalPath, Paths};
+use super::{CausalEdge, CausalGraph, CausalGraphExt, EdgeIndex, GraphKind, NodeIndex};
 
 /// vertex type
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum VertexType {
     /// Default vertex type
     None,
@@ -21,8 +29,8 @@ pub enum VertexType {
     Unobserved,
 }
 
-impl ToString for VertexType {
-    fn to_string(&self) -> String {
+impl Display for VertexType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let result = match &self {
             VertexType::None => "",
             VertexType::Adjusted => "adjusted",
@@ -32,32 +40,129 @@ impl ToString for VertexType {
             VertexType::Unobserved => "unobserved",
         };
 
-        result.to_string()
+        write!(f, "{}", result)
     }
 }
 
 /// edge type
-#[derive(Clone, Copy, Debug, PartialEq)]
+///
+/// Edges are stored from source to target, and the type tells the mark at each end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum EdgeType {
-    /// Directed
+    /// Directed, `->`
     Directed,
-    /// Bidirected
-    _Bidirected,
-    /// Undirected
+    /// Bidirected, for latent confounding, `<->`
+    Bidirected,
+    /// Undirected, `--`
     Undirected,
+    /// Circle at the source and arrowhead at the target, `@->`
+    PartiallyDirected,
+    /// Circles at both ends, `@-@`
+    Nondirected,
+    /// Tail at the source and circle at the target, `--@`
+    PartiallyUndirected,
+}
+
+/// Mark at one end of an edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Mark {
+    /// No arrowhead.
+    Tail,
+    /// Arrowhead.
+    Arrow,
+    /// Undetermined mark of partial ancestral graphs.
+    Circle,
+}
+
+impl EdgeType {
+    /// Marks at the source and at the target of the edge.
+    pub fn marks(&self) -> (Mark, Mark) {
+        match self {
+            EdgeType::Directed => (Mark::Tail, Mark::Arrow),
+            EdgeType::Bidirected => (Mark::Arrow, Mark::Arrow),
+            EdgeType::Undirected => (Mark::Tail, Mark::Tail),
+            EdgeType::PartiallyDirected => (Mark::Circle, Mark::Arrow),
+            EdgeType::Nondirected => (Mark::Circle, Mark::Circle),
+            EdgeType::PartiallyUndirected => (Mark::Tail, Mark::Circle),
+        }
+    }
+
+    /// Edge type with the given marks at the source and at the target, and whether
+    /// the edge has to be stored from target to source to get them.
+    pub fn from_marks(source: Mark, target: Mark) -> (EdgeType, bool) {
+        match (source, target) {
+            (Mark::Tail, Mark::Arrow) => (EdgeType::Directed, false),
+            (Mark::Arrow, Mark::Tail) => (EdgeType::Directed, true),
+            (Mark::Arrow, Mark::Arrow) => (EdgeType::Bidirected, false),
+            (Mark::Tail, Mark::Tail) => (EdgeType::Undirected, false),
+            (Mark::Circle, Mark::Arrow) => (EdgeType::PartiallyDirected, false),
+            (Mark::Arrow, Mark::Circle) => (EdgeType::PartiallyDirected, true),
+            (Mark::Circle, Mark::Circle) => (EdgeType::Nondirected, false),
+            (Mark::Tail, Mark::Circle) => (EdgeType::PartiallyUndirected, false),
+            (Mark::Circle, Mark::Tail) => (EdgeType::PartiallyUndirected, true),
+        }
+    }
+}
+
+impl Display for EdgeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let result = match &self {
+            EdgeType::Directed => "->",
+            EdgeType::Bidirected => "<->",
+            EdgeType::Undirected => "--",
+            EdgeType::PartiallyDirected => "@->",
+            EdgeType::Nondirected => "@-@",
+            EdgeType::PartiallyUndirected => "--@",
+        };
+
+        write!(f, "{}", result)
+    }
+}
+
+/// Node identifier for a name read from another format, quoted unless it is
+/// already a valid dagitty identifier.
+pub fn identifier(name: &str) -> String {
+    let mut chars = name.chars();
+    let plain = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let digits = name.strip_prefix('-').unwrap_or(name);
+    let numeral = digits.chars().any(|c| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.matches('.').count() <= 1;
+    if plain || numeral {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\\\""))
+    }
+}
+
+/// Name of a node for other formats, the inverse of [`identifier`].
+pub fn name(id: &str) -> String {
+    match id.strip_prefix('"').and_then(|id| id.strip_suffix('"')) {
+        Some(quoted) => quoted.replace("\\\"", "\""),
+        None => id.to_string(),
+    }
 }
 
 /// Node information to represent a vertex.
 #[derive(Clone, Debug)]
 pub struct NodeInfo {
     /// Node identifier.
-    pub id: String,
+    pub id: Mutable<String>,
     /// Node layout position.
     pub layout_pos: Mutable<Point<f64>>,
     /// Vertex type.
     pub vertex_type: Mutable<VertexType>,
     /// Vertex path html id
     pub vertex_path_id: Mutable<String>,
+    /// Attributes the parser doesn't interpret, as key and value pairs.
+    ///
+    /// Flags without a value have an empty value.
+    pub attributes: Vec<(String, String)>,
 }
 
 impl NodeInfo {
@@ -65,34 +170,54 @@ impl NodeInfo {
     /// Create a new vertex.
     pub fn new(id: &str, layout_pos_x: f64, layout_pos_y: f64, vertex_type: VertexType) -> Self {
         NodeInfo {
-            id: id.to_string(),
+            id: Mutable::new(id.to_string()),
             layout_pos: Mutable::new(Point::new(layout_pos_x, layout_pos_y)),
             vertex_type: Mutable::new(vertex_type),
-            vertex_path_id: Mutable::new(format!(
-                "{}{}",
-                NodeInfo::DEFAULT_HTML_PATH_ID_PREFIX,
-                id
-            )),
+            vertex_path_id: Mutable::new(NodeInfo::path_id(id)),
+            attributes: Vec::new(),
         }
     }
+
+    fn path_id(id: &str) -> String {
+        format!("{}{}", NodeInfo::DEFAULT_HTML_PATH_ID_PREFIX, id)
+    }
+
+    /// Change the identifier of the vertex, along with its path html id.
+    pub fn rename(&self, id: &str) {
+        self.id.set(id.to_string());
+        self.vertex_path_id.set(NodeInfo::path_id(id));
+    }
+}
+
+impl PartialEq for NodeInfo {
+    fn eq(&self, other: &Self) -> bool {
+        *self.id.lock_ref() == *other.id.lock_ref()
+            && *self.layout_pos.lock_ref() == *other.layout_pos.lock_ref()
+            && *self.vertex_type.lock_ref() == *other.vertex_type.lock_ref()
+            && self.attributes == other.attributes
+    }
 }
 
-impl ToString for NodeInfo {
-    fn to_string(&self) -> String {
-        if *self.vertex_type.lock_ref() == VertexType::None {
-            format!(
-                r#"{} [pos="{}"]"#,
-                self.id,
-                self.layout_pos.lock_ref().to_string()
-            )
+/// Attributes in dagitty notation, `key=value` or `key` for flags.
+fn attribute_strings(attributes: &[(String, String)]) -> impl Iterator<Item = String> + '_ {
+    attributes.iter().map(|(key, value)| {
+        if value.is_empty() {
+            key.clone()
         } else {
-            format!(
-                r#"{} [{},pos="{}"]"#,
-                self.id,
-                self.vertex_type.lock_ref().to_string(),
-                self.layout_pos.lock_ref().to_string()
-            )
+            format!("{}={}", key, value)
+        }
+    })
+}
+
+impl Display for NodeInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut items = Vec::new();
+        if *self.vertex_type.lock_ref() != VertexType::None {
+            items.push(self.vertex_type.lock_ref().to_string());
         }
+        items.push(format!(r#"pos="{}""#, *self.layout_pos.lock_ref()));
+        items.extend(attribute_strings(&self.attributes));
+        write!(f, "{} [{}]", *self.id.lock_ref(), items.join(","))
     }
 }
 
@@ -105,6 +230,8 @@ pub struct EdgeInfo {
     pub layout_pos: Mutable<Option<Point<f64>>>,
     /// Edge type.
     pub edge_type: Mutable<EdgeType>,
+    /// Attributes the parser doesn't interpret, as key and value pairs.
+    pub attributes: Vec<(String, String)>,
 }
 
 impl EdgeInfo {
@@ -114,69 +241,329 @@ impl EdgeInfo {
             _id: id.to_string(),
             layout_pos: Mutable::new(layout_pos),
             edge_type: Mutable::new(edge_type),
+            attributes: Vec::new(),
         }
     }
 }
 
-impl ToString for EdgeInfo {
-    fn to_string(&self) -> String {
-        if self.layout_pos.lock_ref().is_some() {
-            format!(" [pos={}]", self.layout_pos.lock_ref().unwrap().to_string())
+impl PartialEq for EdgeInfo {
+    fn eq(&self, other: &Self) -> bool {
+        *self.layout_pos.lock_ref() == *other.layout_pos.lock_ref()
+            && *self.edge_type.lock_ref() == *other.edge_type.lock_ref()
+            && self.attributes == other.attributes
+    }
+}
+
+impl CausalEdge for EdgeInfo {
+    fn edge_type(&self) -> EdgeType {
+        *self.edge_type.lock_ref()
+    }
+}
+
+impl Display for EdgeInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut items = Vec::new();
+        if let Some(pos) = *self.layout_pos.lock_ref() {
+            items.push(format!(r#"pos="{}""#, pos));
+        }
+        items.extend(attribute_strings(&self.attributes));
+        if items.is_empty() {
+            Ok(())
         } else {
-            "".to_string()
+            write!(f, " [{}]", items.join(","))
         }
     }
 }
 
-impl ToString for CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>> {
-    fn to_string(&self) -> String {
-        let mut result = match self {
-            CausalGraph::Dag(_dag) => "dag {\n".to_string(),
-            CausalGraph::Ungraph(_g) => "graph {\n".to_string(),
-        };
+impl CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>> {
+    /// Bounding box given by the `bb` option as minimum and maximum corners.
+    ///
+    /// Returns `None` when the option is missing or it isn't four numbers.
+    pub fn bounding_box(&self) -> Option<(Point<f64>, Point<f64>)> {
+        let bb = self.attributes.get("bb")?.replace('"', "");
+        let values = bb
+            .split(',')
+            .map(|v| v.trim().parse::<f64>().ok())
+            .collect::<Option<Vec<f64>>>()?;
+        match values[..] {
+            [min_x, min_y, max_x, max_y] => {
+                Some((Point::new(min_x, min_y), Point::new(max_x, max_y)))
+            }
+            _ => None,
+        }
+    }
 
-        match self {
-            CausalGraph::Dag(g) => {
-                for node_index in g.node_indices() {
-                    let node = g.node_weight(node_index).unwrap();
-                    result.push_str(&node.to_string());
-                    result.push('\n');
-                }
-
-                for edge_index in g.edge_indices() {
-                    let edge = g.edge_weight(edge_index).unwrap();
-                    let (source, dst) = g.edge_endpoints(edge_index).unwrap();
-                    result.push_str(&format!(
-                        "{} -> {}",
-                        g.node_weight(source).unwrap().id,
-                        g.node_weight(dst).unwrap().id
-                    ));
-                    result.push_str(&edge.to_string());
-                    result.push('\n');
-                }
+    /// Return the nodes with the given vertex type.
+    pub fn nodes_of_type(&self, vertex_type: VertexType) -> Vec<NodeIndex> {
+        self.node_indices()
+            .into_iter()
+            .filter(|i| *self.node_weight(*i).unwrap().vertex_type.lock_ref() == vertex_type)
+            .collect()
+    }
+
+    /// Place the nodes top to bottom in causal order, one unit apart, for graphs
+    /// read without positions. Nodes of graphs with directed cycles go in one row.
+    pub fn layout_layers(&self) {
+        let layers = match &self.kind {
+            GraphKind::Dag(g) | GraphKind::Pdag(g) | GraphKind::Mag(g) | GraphKind::Pag(g) => {
+                g.causal_order_layers()
+            }
+            GraphKind::Ungraph(g) => g.causal_order_layers(),
+        }
+        .unwrap_or_else(|_| vec![self.node_indices()]);
+        for (y, layer) in layers.iter().enumerate() {
+            let offset = (layer.len() as f64 - 1.0) / 2.0;
+            for (x, node) in layer.iter().enumerate() {
+                let pos = Point::new(x as f64 - offset, y as f64);
+                self.node_weight(*node).unwrap().layout_pos.set(pos);
+            }
+        }
+    }
+
+    /// Node with the given identifier, quoted or not.
+    pub fn node_by_id(&self, id: &str) -> Option<NodeIndex> {
+        let quoted = format!("\"{}\"", id.replace('"', "\\\""));
+        self.node_indices().into_iter().find(|n| {
+            let node_id = self.node_weight(*n).unwrap().id.lock_ref();
+            *node_id == id || *node_id == quoted
+        })
+    }
+
+    /// Whether the nodes `x` and `y` are d-separated given the nodes `z`.
+    pub fn d_separated(&self, x: &[NodeIndex], y: &[NodeIndex], z: &[NodeIndex]) -> bool {
+        match &self.kind {
+            GraphKind::Dag(g) | GraphKind::Pdag(g) | GraphKind::Mag(g) | GraphKind::Pag(g) => {
+                g.d_separated(x, y, z)
             }
-            CausalGraph::Ungraph(g) => {
-                for node_index in g.node_indices() {
-                    let node = g.node_weight(node_index).unwrap();
-                    result.push_str(&node.to_string());
-                    result.push('\n');
-                }
-
-                for edge_index in g.edge_indices() {
-                    let edge = g.edge_weight(edge_index).unwrap();
-                    let (source, dst) = g.edge_endpoints(edge_index).unwrap();
-                    result.push_str(&format!(
-                        "{} -- {}",
-                        g.node_weight(source).unwrap().id,
-                        g.node_weight(dst).unwrap().id
-                    ));
-                    result.push_str(&edge.to_string());
-                    result.push('\n');
-                }
+            GraphKind::Ungraph(g) => g.d_separated(x, y, z),
+        }
+    }
+
+    /// Adjustment sets for the total effect of the exposure nodes on the outcome nodes.
+    ///
+    /// Unobserved nodes are never adjusted for. Returns `None` for graphs other than DAGs.
+    pub fn adjustment_sets(&self) -> Option<AdjustmentSets> {
+        match &self.kind {
+            GraphKind::Dag(g) => Some(adjustment_sets(
+                g,
+                &self.nodes_of_type(VertexType::Exposure),
+                &self.nodes_of_type(VertexType::Outcome),
+                &self.nodes_of_type(VertexType::Unobserved),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Check whether the adjusted nodes form a valid adjustment set for the total
+    /// effect of the exposure nodes on the outcome nodes.
+    ///
+    /// Returns `None` for graphs other than DAGs or when exposure or outcome are missing.
+    pub fn check_adjustment(&self) -> Option<AdjustmentCheck> {
+        let exposures = self.nodes_of_type(VertexType::Exposure);
+        let outcomes = self.nodes_of_type(VertexType::Outcome);
+        match &self.kind {
+            GraphKind::Dag(g) if !exposures.is_empty() && !outcomes.is_empty() => {
+                Some(check_adjustment_set(
+                    g,
+                    &exposures,
+                    &outcomes,
+                    &self.nodes_of_type(VertexType::Adjusted),
+                ))
             }
+            _ => None,
+        }
+    }
+
+    /// Paths from the exposure nodes to the outcome nodes, classified given the
+    /// adjusted and selected nodes.
+    ///
+    /// Returns `None` for graphs other than DAGs.
+    pub fn paths(&self) -> Option<Paths<'_, Arc<NodeInfo>, Arc<EdgeInfo>, u32>> {
+        let mut conditioned = self.nodes_of_type(VertexType::Adjusted);
+        conditioned.extend(self.nodes_of_type(VertexType::Selected));
+        match &self.kind {
+            GraphKind::Dag(g) => Some(Paths::new(
+                g,
+                &self.nodes_of_type(VertexType::Exposure),
+                &self.nodes_of_type(VertexType::Outcome),
+                &conditioned,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Edges lying on an open non-causal path from the exposure nodes to the
+    /// outcome nodes, sorted.
+    pub fn biasing_edges(&self) -> Vec<EdgeIndex> {
+        let mut edges: Vec<EdgeIndex> = self
+            .paths()
+            .into_iter()
+            .flatten()
+            .filter(|path| !path.causal && path.open)
+            .flat_map(|path| path.edges)
+            .collect();
+        edges.sort();
+        edges.dedup();
+        edges
+    }
+
+    /// Cycle of directed edges in a DAG, as its nodes in edge order.
+    ///
+    /// Returns `None` for acyclic DAGs and graphs other than DAGs.
+    pub fn find_cycle(&self) -> Option<Vec<NodeIndex>> {
+        match &self.kind {
+            GraphKind::Dag(g) => g.find_cycle(),
+            _ => None,
+        }
+    }
+
+    /// Cycle a new directed edge from `source` to `target` would close in a DAG,
+    /// starting at `source`.
+    ///
+    /// Returns `None` when the DAG would stay acyclic and for graphs other than DAGs.
+    pub fn closes_cycle(&self, source: NodeIndex, target: NodeIndex) -> Option<Vec<NodeIndex>> {
+        match &self.kind {
+            GraphKind::Dag(g) => g.closes_cycle(source, target),
+            _ => None,
+        }
+    }
+
+    /// Directed edges along the cycle found by [`Self::find_cycle`], sorted.
+    pub fn cycle_edges(&self) -> Vec<EdgeIndex> {
+        let (Some(cycle), GraphKind::Dag(g)) = (self.find_cycle(), &self.kind) else {
+            return Vec::new();
         };
+        let mut edges: Vec<EdgeIndex> = cycle
+            .iter()
+            .zip(cycle.iter().cycle().skip(1))
+            .filter_map(|(source, target)| {
+                g.edges_connecting(*source, *target)
+                    .find(|e| e.weight().edge_type() == EdgeType::Directed)
+                    .map(|e| e.id())
+            })
+            .collect();
+        edges.sort();
+        edges
+    }
 
-        result.push_str("}");
+    /// Format a list of nodes as a chain of directed edges, closing the cycle.
+    pub fn cycle_to_string(&self, cycle: &[NodeIndex]) -> String {
+        let ids: Vec<String> = cycle
+            .iter()
+            .chain(cycle.first())
+            .map(|n| self.node_weight(*n).unwrap().id.get_cloned())
+            .collect();
+        ids.join(" -> ")
+    }
+
+    /// Format a path with node identifiers and the edges as seen walking it.
+    pub fn path_to_string(&self, path: &CausalPath) -> String {
+        let mut result = self.node_weight(path.nodes[0]).unwrap().id.get_cloned();
+        for (edge, node) in path.edges.iter().zip(path.nodes.iter().skip(1)) {
+            let forward = self.edge_endpoints(*edge).map(|(_, t)| t) == Some(*node);
+            let edge_type = self.edge_weight(*edge).unwrap().edge_type();
+            match edge_type {
+                EdgeType::Directed if !forward => result.push_str(" <- "),
+                EdgeType::PartiallyDirected if !forward => result.push_str(" <-@ "),
+                EdgeType::PartiallyUndirected if !forward => result.push_str(" @-- "),
+                _ => result.push_str(&format!(" {} ", edge_type)),
+            }
+            result.push_str(&self.node_weight(*node).unwrap().id.lock_ref());
+        }
         result
     }
+
+    /// Format a list of nodes as their identifiers separated by commas.
+    pub fn nodes_to_string(&self, nodes: &[NodeIndex]) -> String {
+        let ids: Vec<String> = nodes
+            .iter()
+            .map(|n| self.node_weight(*n).unwrap().id.get_cloned())
+            .collect();
+        ids.join(", ")
+    }
+
+    /// Basis set of testable implications, skipping unobserved nodes.
+    ///
+    /// Returns `None` for graphs other than DAGs.
+    pub fn basis_implications(&self) -> Option<Vec<Independence>> {
+        match &self.kind {
+            GraphKind::Dag(g) => Some(basis_implications(
+                g,
+                &self.nodes_of_type(VertexType::Unobserved),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Minimal testable implications, skipping unobserved nodes.
+    ///
+    /// Returns `None` for graphs other than DAGs.
+    pub fn minimal_implications(&self) -> Option<Vec<Independence>> {
+        match &self.kind {
+            GraphKind::Dag(g) => Some(minimal_implications(
+                g,
+                &self.nodes_of_type(VertexType::Unobserved),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Format an independence statement with node identifiers, in dagitty notation.
+    pub fn independence_to_string(&self, independence: &Independence) -> String {
+        let id = |n: &NodeIndex| self.node_weight(*n).unwrap().id.get_cloned();
+        let y: Vec<String> = independence.y.iter().map(id).collect();
+        let mut result = format!("{} _||_ {}", id(&independence.x), y.join(", "));
+        if !independence.given.is_empty() {
+            let given: Vec<String> = independence.given.iter().map(id).collect();
+            result.push_str(&format!(" | {}", given.join(", ")));
+        }
+        result
+    }
+}
+
+impl Display for CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut result = String::new();
+        if self.strict {
+            result.push_str("strict ");
+        }
+        result.push_str(match self.kind {
+            GraphKind::Dag(_) => "dag",
+            GraphKind::Ungraph(_) => "graph",
+            GraphKind::Pdag(_) => "pdag",
+            GraphKind::Mag(_) => "mag",
+            GraphKind::Pag(_) => "pag",
+        });
+        if let Some(name) = &self.name {
+            result.push(' ');
+            result.push_str(name);
+        }
+        result.push_str(" {\n");
+
+        for (key, value) in &self.attributes {
+            result.push_str(&format!("{}={}\n", key, value));
+        }
+
+        for node_index in self.node_indices() {
+            let node = self.node_weight(node_index).unwrap();
+            result.push_str(&node.to_string());
+            result.push('\n');
+        }
+
+        for edge_index in self.edge_indices() {
+            let edge = self.edge_weight(edge_index).unwrap();
+            let (source, dst) = self.edge_endpoints(edge_index).unwrap();
+            result.push_str(&format!(
+                "{} {} {}",
+                *self.node_weight(source).unwrap().id.lock_ref(),
+                edge.edge_type(),
+                *self.node_weight(dst).unwrap().id.lock_ref()
+            ));
+            result.push_str(&edge.to_string());
+            result.push('\n');
+        }
+
+        result.push('}');
+        write!(f, "{}", result)
+    }
 }
@@ -0,0 +1,233 @@
+use std::collections::HashSet;
+
+use super::paths::descendant_set;
+use super::separation::{ancestral_set, minimal_separators, moral_graph};
+use super::{
+    bidirected_neighbors, directed_neighbors, CausalEdge, DefaultIx, Directed, Incoming, IndexType,
+    NodeIndex, StableGraph,
+};
+
+/// Conditional independence statement `x ⊥ y | given`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Independence<Ix = DefaultIx> {
+    /// Left hand side node.
+    pub x: NodeIndex<Ix>,
+    /// Nodes independent of `x`, sorted.
+    pub y: Vec<NodeIndex<Ix>>,
+    /// Conditioning set, sorted.
+    pub given: Vec<NodeIndex<Ix>>,
+}
+
+/// Minimal sets of observed nodes d-separating `u` from `v`.
+fn observed_separators<N, E: CausalEdge, Ix: IndexType>(
+    g: &StableGraph<N, E, Directed, Ix>,
+    u: NodeIndex<Ix>,
+    v: NodeIndex<Ix>,
+    latent: &HashSet<NodeIndex<Ix>>,
+) -> Vec<Vec<NodeIndex<Ix>>> {
+    let ancestors = ancestral_set(&HashSet::from([u, v]), |n| {
+        directed_neighbors(g, n, Incoming)
+    });
+    let moral = moral_graph(
+        &ancestors,
+        |n| directed_neighbors(g, n, Incoming),
+        |n| bidirected_neighbors(g, n),
+    );
+    let allowed = ancestors
+        .iter()
+        .filter(|n| **n != u && **n != v && !latent.contains(n))
+        .copied()
+        .collect();
+    minimal_separators(&moral, &HashSet::from([u]), &HashSet::from([v]), &allowed)
+}
+
+/// Basis set of testable implications following the local Markov property.
+///
+/// Every observed node is independent of its non-descendants given its parents.
+/// Nodes with an unobserved parent or a bidirected edge fall back to the smallest
+/// observed separator for each of their non-descendants, skipping pairs that can't
+/// be separated.
+pub fn basis_implications<N, E: CausalEdge, Ix: IndexType>(
+    g: &StableGraph<N, E, Directed, Ix>,
+    latent: &[NodeIndex<Ix>],
+) -> Vec<Independence<Ix>> {
+    let latent: HashSet<NodeIndex<Ix>> = latent.iter().copied().collect();
+    let mut result = vec![];
+
+    for v in g.node_indices().filter(|n| !latent.contains(n)) {
+        let descendants = descendant_set(g, &HashSet::from([v]));
+        let parents = directed_neighbors(g, v, Incoming);
+        let non_descendants = g
+            .node_indices()
+            .filter(|n| !descendants.contains(n) && !parents.contains(n) && !latent.contains(n));
+
+        if parents.iter().all(|p| !latent.contains(p)) && bidirected_neighbors(g, v).is_empty() {
+            let y: Vec<NodeIndex<Ix>> = non_descendants.collect();
+            if !y.is_empty() {
+                result.push(Independence {
+                    x: v,
+                    y,
+                    given: parents,
+                });
+            }
+        } else {
+            for w in non_descendants {
+                let separator = observed_separators(g, v, w, &latent)
+                    .into_iter()
+                    .min_by_key(|s| s.len());
+                if let Some(given) = separator {
+                    result.push(Independence {
+                        x: v,
+                        y: vec![w],
+                        given,
+                    });
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Minimal testable implications: for every pair of non-adjacent observed nodes,
+/// one statement for each minimal set of observed nodes separating them.
+pub fn minimal_implications<N, E: CausalEdge, Ix: IndexType>(
+    g: &StableGraph<N, E, Directed, Ix>,
+    latent: &[NodeIndex<Ix>],
+) -> Vec<Independence<Ix>> {
+    let latent: HashSet<NodeIndex<Ix>> = latent.iter().copied().collect();
+    let observed: Vec<NodeIndex<Ix>> = g.node_indices().filter(|n| !latent.contains(n)).collect();
+    let mut result = vec![];
+
+    for (i, u) in observed.iter().enumerate() {
+        for v in observed.iter().skip(i + 1) {
+            if g.find_edge_undirected(*u, *v).is_some() {
+                continue;
+            }
+            for given in observed_separators(g, *u, *v, &latent) {
+                result.push(Independence {
+                    x: *u,
+                    y: vec![*v],
+                    given,
+                });
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::dagitty::EdgeType as Edge;
+    use crate::graph::CausalGraphExt;
+
+    fn m_bias() -> (StableGraph<&'static str, Edge>, Vec<NodeIndex>) {
+        let mut g = StableGraph::<&str, Edge>::new();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let d = g.add_node("D");
+        let e = g.add_node("E");
+        let z = g.add_node("Z");
+        g.add_edge(a, e, Edge::Directed);
+        g.add_edge(a, z, Edge::Directed);
+        g.add_edge(b, d, Edge::Directed);
+        g.add_edge(b, z, Edge::Directed);
+        g.add_edge(e, d, Edge::Directed);
+        (g, vec![a, b, d, e, z])
+    }
+
+    #[test]
+    fn test_basis_implications() {
+        let (g, n) = m_bias();
+        let (a, b, d, e, z) = (n[0], n[1], n[2], n[3], n[4]);
+
+        let implications = basis_implications(&g, &[]);
+        assert_eq!(
+            vec![
+                Independence {
+                    x: a,
+                    y: vec![b],
+                    given: vec![]
+                },
+                Independence {
+                    x: b,
+                    y: vec![a, e],
+                    given: vec![]
+                },
+                Independence {
+                    x: d,
+                    y: vec![a, z],
+                    given: vec![b, e]
+                },
+                Independence {
+                    x: e,
+                    y: vec![b, z],
+                    given: vec![a]
+                },
+                Independence {
+                    x: z,
+                    y: vec![d, e],
+                    given: vec![a, b]
+                },
+            ],
+            implications
+        );
+        for i in implications {
+            assert!(g.d_separated(&[i.x], &i.y, &i.given));
+        }
+    }
+
+    #[test]
+    fn test_minimal_implications() {
+        let (g, n) = m_bias();
+        let (a, b, d, e, z) = (n[0], n[1], n[2], n[3], n[4]);
+
+        let implications = minimal_implications(&g, &[]);
+        assert!(implications.contains(&Independence {
+            x: a,
+            y: vec![b],
+            given: vec![]
+        }));
+        assert!(implications.contains(&Independence {
+            x: a,
+            y: vec![d],
+            given: vec![e]
+        }));
+        assert!(implications.contains(&Independence {
+            x: d,
+            y: vec![z],
+            given: vec![a, b]
+        }));
+        assert!(implications.contains(&Independence {
+            x: d,
+            y: vec![z],
+            given: vec![b, e]
+        }));
+        for i in implications.iter() {
+            assert!(g.d_separated(&[i.x], &i.y, &i.given));
+        }
+
+        // With A unobserved, E and Z can't be separated anymore.
+        let implications = minimal_implications(&g, &[a]);
+        assert!(!implications.iter().any(|i| i.x == e && i.y == vec![z]));
+        assert!(implications.iter().all(|i| !i.given.contains(&a)));
+    }
+
+    #[test]
+    fn test_basis_implications_latent_parent() {
+        let (g, n) = m_bias();
+        let (a, b, e, z) = (n[0], n[1], n[3], n[4]);
+
+        let implications = basis_implications(&g, &[a]);
+        assert!(implications.iter().all(|i| i.x != a && !i.y.contains(&a)));
+        // E has the latent parent A: E ⊥ B holds without conditioning, E ⊥ Z can't be tested.
+        assert!(implications.contains(&Independence {
+            x: e,
+            y: vec![b],
+            given: vec![]
+        }));
+        assert!(!implications.iter().any(|i| i.x == e && i.y == vec![z]));
+    }
+}
@@ -1,26 +1,70 @@
+/// Covariate adjustment for total effects.
+pub mod adjustment;
 /// Common data structures for dagitty interaction.
 pub mod dagitty;
+/// Testable implications of causal graphs.
+pub mod implications;
+/// Paths between exposures and outcomes.
+pub mod paths;
+/// JSON schema of causal models.
+#[cfg(feature = "serde")]
+pub mod schema;
+mod separation;
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::fmt::Debug;
+use std::sync::Arc;
 
 use petgraph::visit::EdgeRef;
 pub use petgraph::{
-    graph::{DiGraph, Edges, UnGraph},
-    graph::{EdgeIndex, NodeIndex},
-    graph::{IndexType, WalkNeighbors},
-    stable_graph::DefaultIx,
-    Directed,
-    Direction::Incoming,
-    EdgeType, Graph, Undirected,
+    stable_graph::{DefaultIx, Edges, WalkNeighbors},
+    stable_graph::{EdgeIndex, NodeIndex},
+    stable_graph::{IndexType, StableDiGraph, StableGraph, StableUnGraph},
+    Directed, Direction,
+    Direction::{Incoming, Outgoing},
+    EdgeType, Undirected,
 };
 
-/// Causal Graph
-pub enum CausalGraph<N, E, Ix = DefaultIx> {
+/// Storage for each kind of causal graph.
+///
+/// Mixed graphs (`Pdag`, `Mag` and `Pag`) are stored as directed graphs whose edge
+/// weights keep the marks at both ends of every edge.
+pub enum GraphKind<N, E, Ix = DefaultIx> {
     /// Dag
-    Dag(Graph<N, E, Directed, Ix>),
+    Dag(StableGraph<N, E, Directed, Ix>),
     /// Ungraph
-    Ungraph(Graph<N, E, Undirected, Ix>),
+    Ungraph(StableGraph<N, E, Undirected, Ix>),
+    /// Partially directed acyclic graph
+    Pdag(StableGraph<N, E, Directed, Ix>),
+    /// Maximal ancestral graph
+    Mag(StableGraph<N, E, Directed, Ix>),
+    /// Partial ancestral graph
+    Pag(StableGraph<N, E, Directed, Ix>),
+}
+
+impl<N, E, Ix: IndexType> Debug for GraphKind<N, E, Ix> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            Self::Dag(_) => "Dag",
+            Self::Ungraph(_) => "Ungraph",
+            Self::Pdag(_) => "Pdag",
+            Self::Mag(_) => "Mag",
+            Self::Pag(_) => "Pag",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// Causal Graph
+pub struct CausalGraph<N, E, Ix = DefaultIx> {
+    /// Graph storage.
+    pub kind: GraphKind<N, E, Ix>,
+    /// Graph name.
+    pub name: Option<String>,
+    /// Strict graphs don't allow multiple edges between the same nodes.
+    pub strict: bool,
+    /// Graph level options, like the bounding box `bb`, as raw key and value pairs.
+    pub attributes: BTreeMap<String, String>,
 }
 
 impl<N, E, Ix: IndexType> Debug for CausalGraph<N, E, Ix> {
@@ -29,111 +73,542 @@ impl<N, E, Ix: IndexType> Debug for CausalGraph<N, E, Ix> {
     }
 }
 
+impl<N, E, Ix: IndexType> From<GraphKind<N, E, Ix>> for CausalGraph<N, E, Ix> {
+    fn from(kind: GraphKind<N, E, Ix>) -> Self {
+        Self {
+            kind,
+            name: None,
+            strict: false,
+            attributes: BTreeMap::new(),
+        }
+    }
+}
+
+impl<N: PartialEq, E: PartialEq, Ix: IndexType> PartialEq for CausalGraph<N, E, Ix> {
+    /// Graphs are equal when they have the same kind and metadata, and the same
+    /// nodes and edges in the same order.
+    fn eq(&self, other: &Self) -> bool {
+        let same_kind = matches!(
+            (&self.kind, &other.kind),
+            (GraphKind::Dag(_), GraphKind::Dag(_))
+                | (GraphKind::Ungraph(_), GraphKind::Ungraph(_))
+                | (GraphKind::Pdag(_), GraphKind::Pdag(_))
+                | (GraphKind::Mag(_), GraphKind::Mag(_))
+                | (GraphKind::Pag(_), GraphKind::Pag(_))
+        );
+        let nodes = self.node_indices();
+        let edges = self.edge_indices();
+
+        same_kind
+            && self.name == other.name
+            && self.strict == other.strict
+            && self.attributes == other.attributes
+            && nodes == other.node_indices()
+            && edges == other.edge_indices()
+            && nodes
+                .iter()
+                .all(|n| self.node_weight(*n) == other.node_weight(*n))
+            && edges.iter().all(|e| {
+                self.edge_endpoints(*e) == other.edge_endpoints(*e)
+                    && self.edge_weight(*e) == other.edge_weight(*e)
+            })
+    }
+}
+
 impl<N, E, Ix: IndexType> CausalGraph<N, E, Ix> {
     /// Add new node to the graph.
     pub fn add_node(&mut self, n: N) -> NodeIndex<Ix> {
-        match self {
-            Self::Dag(g) => g.add_node(n),
-            Self::Ungraph(g) => g.add_node(n),
+        match &mut self.kind {
+            GraphKind::Dag(g) | GraphKind::Pdag(g) | GraphKind::Mag(g) | GraphKind::Pag(g) => {
+                g.add_node(n)
+            }
+            GraphKind::Ungraph(g) => g.add_node(n),
         }
     }
 
     /// Add new edge to the graph.
     pub fn add_edge(&mut self, left: NodeIndex<Ix>, right: NodeIndex<Ix>, e: E) -> EdgeIndex<Ix> {
-        match self {
-            Self::Dag(g) => g.add_edge(left, right, e),
-            Self::Ungraph(g) => g.add_edge(left, right, e),
+        match &mut self.kind {
+            GraphKind::Dag(g) | GraphKind::Pdag(g) | GraphKind::Mag(g) | GraphKind::Pag(g) => {
+                g.add_edge(left, right, e)
+            }
+            GraphKind::Ungraph(g) => g.add_edge(left, right, e),
         }
     }
 
-    /// Edges from  node.
+    /// Edges touching a node, in either direction, sorted.
     pub fn edges(&self, n: NodeIndex<Ix>) -> Vec<EdgeIndex<Ix>> {
-        match self {
-            Self::Dag(g) => g.edges(n).map(|e| e.id()).collect(),
-            Self::Ungraph(g) => g.edges(n).map(|e| e.id()).collect(),
-        }
+        let mut edges: Vec<EdgeIndex<Ix>> = match &self.kind {
+            GraphKind::Dag(g) | GraphKind::Pdag(g) | GraphKind::Mag(g) | GraphKind::Pag(g) => g
+                .edges_directed(n, Outgoing)
+                .chain(g.edges_directed(n, Incoming))
+                .map(|e| e.id())
+                .collect(),
+            GraphKind::Ungraph(g) => g.edges(n).map(|e| e.id()).collect(),
+        };
+        edges.sort();
+        edges.dedup();
+        edges
     }
 
-    /// Remove node.
+    /// Remove node and its edges, other indices stay valid.
     pub fn remove_node(&mut self, n: NodeIndex<Ix>) -> Option<N> {
-        match self {
-            Self::Dag(g) => g.remove_node(n),
-            Self::Ungraph(g) => g.remove_node(n),
+        match &mut self.kind {
+            GraphKind::Dag(g) | GraphKind::Pdag(g) | GraphKind::Mag(g) | GraphKind::Pag(g) => {
+                g.remove_node(n)
+            }
+            GraphKind::Ungraph(g) => g.remove_node(n),
         }
     }
+
+    /// Remove edge, other indices stay valid.
+    pub fn remove_edge(&mut self, e: EdgeIndex<Ix>) -> Option<E> {
+        match &mut self.kind {
+            GraphKind::Dag(g) | GraphKind::Pdag(g) | GraphKind::Mag(g) | GraphKind::Pag(g) => {
+                g.remove_edge(e)
+            }
+            GraphKind::Ungraph(g) => g.remove_edge(e),
+        }
+    }
+
+    /// Edge from `left` to `right`, in either direction for undirected graphs.
+    pub fn find_edge(&self, left: NodeIndex<Ix>, right: NodeIndex<Ix>) -> Option<EdgeIndex<Ix>> {
+        match &self.kind {
+            GraphKind::Dag(g) | GraphKind::Pdag(g) | GraphKind::Mag(g) | GraphKind::Pag(g) => {
+                g.find_edge(left, right)
+            }
+            GraphKind::Ungraph(g) => g.find_edge(left, right),
+        }
+    }
+
+    /// All node indices.
+    pub fn node_indices(&self) -> Vec<NodeIndex<Ix>> {
+        match &self.kind {
+            GraphKind::Dag(g) | GraphKind::Pdag(g) | GraphKind::Mag(g) | GraphKind::Pag(g) => {
+                g.node_indices().collect()
+            }
+            GraphKind::Ungraph(g) => g.node_indices().collect(),
+        }
+    }
+
+    /// All edge indices.
+    pub fn edge_indices(&self) -> Vec<EdgeIndex<Ix>> {
+        match &self.kind {
+            GraphKind::Dag(g) | GraphKind::Pdag(g) | GraphKind::Mag(g) | GraphKind::Pag(g) => {
+                g.edge_indices().collect()
+            }
+            GraphKind::Ungraph(g) => g.edge_indices().collect(),
+        }
+    }
+
+    /// Node weight.
+    pub fn node_weight(&self, n: NodeIndex<Ix>) -> Option<&N> {
+        match &self.kind {
+            GraphKind::Dag(g) | GraphKind::Pdag(g) | GraphKind::Mag(g) | GraphKind::Pag(g) => {
+                g.node_weight(n)
+            }
+            GraphKind::Ungraph(g) => g.node_weight(n),
+        }
+    }
+
+    /// Mutable node weight.
+    pub fn node_weight_mut(&mut self, n: NodeIndex<Ix>) -> Option<&mut N> {
+        match &mut self.kind {
+            GraphKind::Dag(g) | GraphKind::Pdag(g) | GraphKind::Mag(g) | GraphKind::Pag(g) => {
+                g.node_weight_mut(n)
+            }
+            GraphKind::Ungraph(g) => g.node_weight_mut(n),
+        }
+    }
+
+    /// Edge weight.
+    pub fn edge_weight(&self, e: EdgeIndex<Ix>) -> Option<&E> {
+        match &self.kind {
+            GraphKind::Dag(g) | GraphKind::Pdag(g) | GraphKind::Mag(g) | GraphKind::Pag(g) => {
+                g.edge_weight(e)
+            }
+            GraphKind::Ungraph(g) => g.edge_weight(e),
+        }
+    }
+
+    /// Source and target of an edge.
+    pub fn edge_endpoints(&self, e: EdgeIndex<Ix>) -> Option<(NodeIndex<Ix>, NodeIndex<Ix>)> {
+        match &self.kind {
+            GraphKind::Dag(g) | GraphKind::Pdag(g) | GraphKind::Mag(g) | GraphKind::Pag(g) => {
+                g.edge_endpoints(e)
+            }
+            GraphKind::Ungraph(g) => g.edge_endpoints(e),
+        }
+    }
+}
+
+/// Edge weights that know which kind of edge they represent.
+///
+/// Graph algorithms only follow `Directed` edges as parent to child links, and only
+/// arrowheads make colliders: circle marks count as undetermined, never as arrows.
+pub trait CausalEdge {
+    /// Kind of the edge.
+    fn edge_type(&self) -> dagitty::EdgeType;
+}
+
+impl CausalEdge for dagitty::EdgeType {
+    fn edge_type(&self) -> dagitty::EdgeType {
+        *self
+    }
 }
 
-/// Extend Graph with new calls needed by causal graph algorithms.
+impl<T: CausalEdge> CausalEdge for Arc<T> {
+    fn edge_type(&self) -> dagitty::EdgeType {
+        (**self).edge_type()
+    }
+}
+
+/// Extend the petgraph graphs stored in a [`CausalGraph`] with new calls needed by
+/// causal graph algorithms.
+///
+/// On undirected graphs every neighbour is both a parent and a child, so ancestors
+/// and descendants of a node are the nodes of its connected component.
+///
+/// Set-valued variants return the union of the results for every node of the set,
+/// excluding the nodes of the set themselves.
 pub trait CausalGraphExt<'a, N, E, Ty: EdgeType, Ix: IndexType> {
     /// Return all ancestors from a given node.
     fn ancestors(&'a self, node: NodeIndex<Ix>) -> Ancestors<'a, N, E, Ty, Ix>;
 
+    /// Return all ancestors from a set of nodes.
+    fn ancestors_of_set(&'a self, nodes: &[NodeIndex<Ix>]) -> Ancestors<'a, N, E, Ty, Ix>;
+
+    /// Return all descendants from a given node.
+    fn descendants(&'a self, node: NodeIndex<Ix>) -> Descendants<'a, N, E, Ty, Ix>;
+
+    /// Return all descendants from a set of nodes.
+    fn descendants_of_set(&'a self, nodes: &[NodeIndex<Ix>]) -> Descendants<'a, N, E, Ty, Ix>;
+
+    /// Return the parents of a node, sorted.
+    fn parents(&self, node: NodeIndex<Ix>) -> Vec<NodeIndex<Ix>>;
+
+    /// Return the parents of a set of nodes, sorted.
+    fn parents_of_set(&self, nodes: &[NodeIndex<Ix>]) -> Vec<NodeIndex<Ix>>;
+
+    /// Return the children of a node, sorted.
+    fn children(&self, node: NodeIndex<Ix>) -> Vec<NodeIndex<Ix>>;
+
+    /// Return the children of a set of nodes, sorted.
+    fn children_of_set(&self, nodes: &[NodeIndex<Ix>]) -> Vec<NodeIndex<Ix>>;
+
+    /// Return the other parents of the children of a node, sorted.
+    fn spouses(&self, node: NodeIndex<Ix>) -> Vec<NodeIndex<Ix>>;
+
+    /// Return the other parents of the children of a set of nodes, sorted.
+    fn spouses_of_set(&self, nodes: &[NodeIndex<Ix>]) -> Vec<NodeIndex<Ix>>;
+
+    /// Return the nodes adjacent to a node, sorted.
+    fn neighbours(&self, node: NodeIndex<Ix>) -> Vec<NodeIndex<Ix>>;
+
+    /// Return the nodes adjacent to a set of nodes, sorted.
+    fn neighbours_of_set(&self, nodes: &[NodeIndex<Ix>]) -> Vec<NodeIndex<Ix>>;
+
+    /// Return the parents, children and spouses of a node, sorted.
+    fn markov_blanket(&self, node: NodeIndex<Ix>) -> Vec<NodeIndex<Ix>>;
+
+    /// Return the parents, children and spouses of a set of nodes, sorted.
+    fn markov_blanket_of_set(&self, nodes: &[NodeIndex<Ix>]) -> Vec<NodeIndex<Ix>>;
+
+    /// Return true if node sets `x` and `y` are d-separated given `z`.
+    fn d_separated(&self, x: &[NodeIndex<Ix>], y: &[NodeIndex<Ix>], z: &[NodeIndex<Ix>]) -> bool;
+
+    /// Return a cycle of directed edges as its nodes in edge order, without repeating
+    /// the first node, or `None` when the graph is acyclic. Undirected graphs have no
+    /// directed cycles.
+    fn find_cycle(&self) -> Option<Vec<NodeIndex<Ix>>>;
+
+    /// Return the cycle a new directed edge from `source` to `target` would close,
+    /// starting at `source`, or `None` when the graph would stay acyclic.
+    fn closes_cycle(
+        &self,
+        source: NodeIndex<Ix>,
+        target: NodeIndex<Ix>,
+    ) -> Option<Vec<NodeIndex<Ix>>>;
+
+    /// Return the nodes sorted so that every node comes after its parents, or the
+    /// cycle that makes it impossible.
+    fn topological_sort(&self) -> Result<Vec<NodeIndex<Ix>>, Vec<NodeIndex<Ix>>>;
+
+    /// Return the nodes grouped by causal order: roots first, then each node in the
+    /// layer of the longest directed path reaching it from a root. Layers are sorted.
+    /// Fails with the cycle that makes the order impossible.
+    fn causal_order_layers(&self) -> Result<Vec<Vec<NodeIndex<Ix>>>, Vec<NodeIndex<Ix>>>;
+
+    /// Return true if the set contains the ancestors of all of its nodes.
+    fn is_ancestral_set(&self, nodes: &[NodeIndex<Ix>]) -> bool;
+
     /// Add edges
     fn add_edges(&mut self, edges: impl Iterator<Item = (NodeIndex<Ix>, NodeIndex<Ix>, E)>);
 }
 
-/// Ancestors is an structure containing all nodes that are ancestors of a particular one.
-pub struct Ancestors<'a, N, E, Ty, Ix>
+/// Relatives is an structure containing all nodes reachable from a set of nodes
+/// following edges in one direction.
+pub struct Relatives<'a, N, E, Ty, Ix>
 where
     Ty: EdgeType,
     Ix: IndexType,
 {
-    g: &'a Graph<N, E, Ty, Ix>,
+    g: &'a StableGraph<N, E, Ty, Ix>,
+    direction: Direction,
     visited: HashSet<NodeIndex<Ix>>,
-    pending_neighbors: Vec<WalkNeighbors<Ix>>,
+    pending: Vec<NodeIndex<Ix>>,
 }
 
-impl<'a, N, E, Ty: EdgeType, Ix: IndexType> Ancestors<'a, N, E, Ty, Ix> {
-    fn new(g: &'a Graph<N, E, Ty, Ix>, node: NodeIndex<Ix>) -> Self {
+/// Ancestors is an structure containing all nodes that are ancestors of a particular one.
+pub type Ancestors<'a, N, E, Ty, Ix> = Relatives<'a, N, E, Ty, Ix>;
+
+/// Descendants is an structure containing all nodes that are descendants of a particular one.
+pub type Descendants<'a, N, E, Ty, Ix> = Relatives<'a, N, E, Ty, Ix>;
+
+impl<'a, N, E: CausalEdge, Ty: EdgeType, Ix: IndexType> Relatives<'a, N, E, Ty, Ix> {
+    fn new(
+        g: &'a StableGraph<N, E, Ty, Ix>,
+        nodes: &[NodeIndex<Ix>],
+        direction: Direction,
+    ) -> Self {
+        let visited: HashSet<NodeIndex<Ix>> = nodes.iter().copied().collect();
+        let mut pending = vec![];
+        for node in nodes.iter().rev() {
+            for n in directed_neighbors(g, *node, direction).into_iter().rev() {
+                if !visited.contains(&n) {
+                    pending.push(n);
+                }
+            }
+        }
         Self {
             g,
-            visited: HashSet::from([node]),
-            pending_neighbors: vec![g.neighbors_directed(node, Incoming).detach()],
+            direction,
+            visited,
+            pending,
         }
     }
 }
 
-impl<'a, N, E, Ty: EdgeType, Ix: IndexType> Debug for Ancestors<'a, N, E, Ty, Ix> {
+impl<'a, N, E, Ty: EdgeType, Ix: IndexType> Debug for Relatives<'a, N, E, Ty, Ix> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Ancestors visited: {:?}", self.visited)
+        match self.direction {
+            Incoming => write!(f, "Ancestors visited: {:?}", self.visited),
+            Outgoing => write!(f, "Descendants visited: {:?}", self.visited),
+        }
     }
 }
 
-impl<'a, N, E, Ty: EdgeType, Ix: IndexType> Iterator for Ancestors<'a, N, E, Ty, Ix> {
+impl<'a, N, E: CausalEdge, Ty: EdgeType, Ix: IndexType> Iterator for Relatives<'a, N, E, Ty, Ix> {
     type Item = NodeIndex<Ix>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.pending_neighbors.is_empty() {
-            None
-        } else {
-            let mut found = None;
-            while let Some(mut neighbors) = self.pending_neighbors.pop() {
-                while let Some(edge) = neighbors.next(self.g) {
-                    if !self.visited.contains(&edge.1) {
-                        found = Some(edge.1);
-                        self.visited.insert(edge.1);
-                        break;
-                    }
-                }
-                if let Some(node) = found {
-                    self.pending_neighbors
-                        .push(self.g.neighbors_directed(node, Incoming).detach());
-                    self.pending_neighbors.insert(0, neighbors);
-                    break;
+        while let Some(node) = self.pending.pop() {
+            if !self.visited.insert(node) {
+                continue;
+            }
+            for n in directed_neighbors(self.g, node, self.direction)
+                .into_iter()
+                .rev()
+            {
+                if !self.visited.contains(&n) {
+                    self.pending.push(n);
                 }
             }
-            found
+            return Some(node);
         }
+        None
     }
 }
 
-impl<'a, N, E, Ty, Ix> CausalGraphExt<'a, N, E, Ty, Ix> for Graph<N, E, Ty, Ix>
+impl<'a, N, E, Ty, Ix> CausalGraphExt<'a, N, E, Ty, Ix> for StableGraph<N, E, Ty, Ix>
 where
+    E: CausalEdge,
     Ty: EdgeType,
     Ix: IndexType,
 {
     fn ancestors(&'a self, node: NodeIndex<Ix>) -> Ancestors<'a, N, E, Ty, Ix> {
-        Ancestors::new(self, node)
+        Relatives::new(self, &[node], Incoming)
+    }
+
+    fn ancestors_of_set(&'a self, nodes: &[NodeIndex<Ix>]) -> Ancestors<'a, N, E, Ty, Ix> {
+        Relatives::new(self, nodes, Incoming)
+    }
+
+    fn descendants(&'a self, node: NodeIndex<Ix>) -> Descendants<'a, N, E, Ty, Ix> {
+        Relatives::new(self, &[node], Outgoing)
+    }
+
+    fn descendants_of_set(&'a self, nodes: &[NodeIndex<Ix>]) -> Descendants<'a, N, E, Ty, Ix> {
+        Relatives::new(self, nodes, Outgoing)
+    }
+
+    fn parents(&self, node: NodeIndex<Ix>) -> Vec<NodeIndex<Ix>> {
+        self.parents_of_set(&[node])
+    }
+
+    fn parents_of_set(&self, nodes: &[NodeIndex<Ix>]) -> Vec<NodeIndex<Ix>> {
+        adjacent(self, nodes, &[Incoming])
+    }
+
+    fn children(&self, node: NodeIndex<Ix>) -> Vec<NodeIndex<Ix>> {
+        self.children_of_set(&[node])
+    }
+
+    fn children_of_set(&self, nodes: &[NodeIndex<Ix>]) -> Vec<NodeIndex<Ix>> {
+        adjacent(self, nodes, &[Outgoing])
+    }
+
+    fn spouses(&self, node: NodeIndex<Ix>) -> Vec<NodeIndex<Ix>> {
+        self.spouses_of_set(&[node])
+    }
+
+    fn spouses_of_set(&self, nodes: &[NodeIndex<Ix>]) -> Vec<NodeIndex<Ix>> {
+        let children = self.children_of_set(nodes);
+        let spouses = adjacent(self, &children, &[Incoming]);
+        spouses.into_iter().filter(|n| !nodes.contains(n)).collect()
+    }
+
+    fn neighbours(&self, node: NodeIndex<Ix>) -> Vec<NodeIndex<Ix>> {
+        self.neighbours_of_set(&[node])
+    }
+
+    fn neighbours_of_set(&self, nodes: &[NodeIndex<Ix>]) -> Vec<NodeIndex<Ix>> {
+        let result: BTreeSet<NodeIndex<Ix>> = nodes
+            .iter()
+            .flat_map(|n| self.neighbors_undirected(*n))
+            .filter(|n| !nodes.contains(n))
+            .collect();
+        result.into_iter().collect()
+    }
+
+    fn markov_blanket(&self, node: NodeIndex<Ix>) -> Vec<NodeIndex<Ix>> {
+        self.markov_blanket_of_set(&[node])
+    }
+
+    fn markov_blanket_of_set(&self, nodes: &[NodeIndex<Ix>]) -> Vec<NodeIndex<Ix>> {
+        let mut blanket: BTreeSet<NodeIndex<Ix>> =
+            self.neighbours_of_set(nodes).into_iter().collect();
+        blanket.extend(self.spouses_of_set(nodes));
+        blanket.into_iter().collect()
+    }
+
+    fn d_separated(&self, x: &[NodeIndex<Ix>], y: &[NodeIndex<Ix>], z: &[NodeIndex<Ix>]) -> bool {
+        separation::d_separated(self, x, y, z)
+    }
+
+    fn find_cycle(&self) -> Option<Vec<NodeIndex<Ix>>> {
+        if !self.is_directed() {
+            return None;
+        }
+        let mut finished = HashSet::new();
+        for start in self.node_indices() {
+            if finished.contains(&start) {
+                continue;
+            }
+            // Depth first search keeping the current path and the children left to
+            // visit for each node in it, an edge back into the path closes a cycle.
+            let mut path = vec![start];
+            let mut pending = vec![directed_neighbors(self, start, Outgoing)];
+            while let Some(children) = pending.last_mut() {
+                match children.pop() {
+                    Some(child) => {
+                        if let Some(i) = path.iter().position(|n| *n == child) {
+                            return Some(path.split_off(i));
+                        }
+                        if !finished.contains(&child) {
+                            path.push(child);
+                            pending.push(directed_neighbors(self, child, Outgoing));
+                        }
+                    }
+                    None => {
+                        finished.extend(path.pop());
+                        pending.pop();
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn closes_cycle(
+        &self,
+        source: NodeIndex<Ix>,
+        target: NodeIndex<Ix>,
+    ) -> Option<Vec<NodeIndex<Ix>>> {
+        if !self.is_directed() {
+            return None;
+        }
+        // Breadth first search from target to source, remembering where each node
+        // was reached from to rebuild the shortest path.
+        let mut reached_from = HashMap::new();
+        let mut queue = VecDeque::from([target]);
+        while let Some(node) = queue.pop_front() {
+            if node == source {
+                let mut cycle = vec![];
+                let mut current = node;
+                while current != target {
+                    current = reached_from[&current];
+                    cycle.push(current);
+                }
+                cycle.push(source);
+                cycle.reverse();
+                return Some(cycle);
+            }
+            for child in directed_neighbors(self, node, Outgoing) {
+                if child != target && !reached_from.contains_key(&child) {
+                    reached_from.insert(child, node);
+                    queue.push_back(child);
+                }
+            }
+        }
+        None
+    }
+
+    fn topological_sort(&self) -> Result<Vec<NodeIndex<Ix>>, Vec<NodeIndex<Ix>>> {
+        Ok(self.causal_order_layers()?.into_iter().flatten().collect())
+    }
+
+    fn causal_order_layers(&self) -> Result<Vec<Vec<NodeIndex<Ix>>>, Vec<NodeIndex<Ix>>> {
+        if let Some(cycle) = self.find_cycle() {
+            return Err(cycle);
+        }
+        // Undirected graphs have no edges ordering their nodes.
+        let directed = |node, direction| {
+            if self.is_directed() {
+                directed_neighbors(self, node, direction)
+            } else {
+                vec![]
+            }
+        };
+        // Layer by layer, a node is ready once all its parents are placed.
+        let mut pending_parents: HashMap<NodeIndex<Ix>, usize> = self
+            .node_indices()
+            .map(|n| (n, directed(n, Incoming).len()))
+            .collect();
+        let mut layer: Vec<NodeIndex<Ix>> = self
+            .node_indices()
+            .filter(|n| pending_parents[n] == 0)
+            .collect();
+        let mut layers = vec![];
+        while !layer.is_empty() {
+            let mut next = BTreeSet::new();
+            for node in &layer {
+                for child in directed(*node, Outgoing) {
+                    let count = pending_parents.get_mut(&child).unwrap();
+                    *count -= 1;
+                    if *count == 0 {
+                        next.insert(child);
+                    }
+                }
+            }
+            layers.push(layer);
+            layer = next.into_iter().collect();
+        }
+        Ok(layers)
+    }
+
+    fn is_ancestral_set(&self, nodes: &[NodeIndex<Ix>]) -> bool {
+        self.parents_of_set(nodes).is_empty()
     }
 
     fn add_edges(&mut self, edges: impl Iterator<Item = (NodeIndex<Ix>, NodeIndex<Ix>, E)>) {
@@ -143,13 +618,113 @@ where
     }
 }
 
+/// Nodes joined to `nodes` by a directed edge in any of the `directions`, excluding
+/// `nodes`, sorted.
+fn adjacent<N, E: CausalEdge, Ty: EdgeType, Ix: IndexType>(
+    g: &StableGraph<N, E, Ty, Ix>,
+    nodes: &[NodeIndex<Ix>],
+    directions: &[Direction],
+) -> Vec<NodeIndex<Ix>> {
+    let mut result = BTreeSet::new();
+    for node in nodes {
+        for direction in directions {
+            result.extend(directed_neighbors(g, *node, *direction));
+        }
+    }
+    result.into_iter().filter(|n| !nodes.contains(n)).collect()
+}
+
+/// Nodes joined to `node` by a directed edge in `direction`, sorted.
+///
+/// Every edge of an undirected graph counts in both directions.
+pub(crate) fn directed_neighbors<N, E: CausalEdge, Ty: EdgeType, Ix: IndexType>(
+    g: &StableGraph<N, E, Ty, Ix>,
+    node: NodeIndex<Ix>,
+    direction: Direction,
+) -> Vec<NodeIndex<Ix>> {
+    let mut result: Vec<NodeIndex<Ix>> = if g.is_directed() {
+        g.edges_directed(node, direction)
+            .filter(|e| e.weight().edge_type() == dagitty::EdgeType::Directed)
+            .map(|e| match direction {
+                Incoming => e.source(),
+                Outgoing => e.target(),
+            })
+            .collect()
+    } else {
+        g.neighbors(node).collect()
+    };
+    result.sort();
+    result.dedup();
+    result
+}
+
+/// Nodes joined to `node` by a bidirected edge, sorted.
+pub(crate) fn bidirected_neighbors<N, E: CausalEdge, Ty: EdgeType, Ix: IndexType>(
+    g: &StableGraph<N, E, Ty, Ix>,
+    node: NodeIndex<Ix>,
+) -> Vec<NodeIndex<Ix>> {
+    let mut result: Vec<NodeIndex<Ix>> = incident_edges(g, node)
+        .into_iter()
+        .filter(|(e, _)| g[*e].edge_type() == dagitty::EdgeType::Bidirected)
+        .map(|(_, n)| n)
+        .collect();
+    result.sort();
+    result.dedup();
+    result
+}
+
+/// Edges touching `node` together with the node at their other end.
+pub(crate) fn incident_edges<N, E, Ty: EdgeType, Ix: IndexType>(
+    g: &StableGraph<N, E, Ty, Ix>,
+    node: NodeIndex<Ix>,
+) -> Vec<(EdgeIndex<Ix>, NodeIndex<Ix>)> {
+    if g.is_directed() {
+        g.edges_directed(node, Outgoing)
+            .map(|e| (e.id(), e.target()))
+            .chain(
+                g.edges_directed(node, Incoming)
+                    .map(|e| (e.id(), e.source())),
+            )
+            .collect()
+    } else {
+        g.edges(node)
+            .map(|e| {
+                let other = if e.source() == node {
+                    e.target()
+                } else {
+                    e.source()
+                };
+                (e.id(), other)
+            })
+            .collect()
+    }
+}
+
+/// Return true if `edge` has an arrowhead at its endpoint `node`.
+pub(crate) fn has_arrowhead<N, E: CausalEdge, Ty: EdgeType, Ix: IndexType>(
+    g: &StableGraph<N, E, Ty, Ix>,
+    edge: EdgeIndex<Ix>,
+    node: NodeIndex<Ix>,
+) -> bool {
+    if !g.is_directed() {
+        return false;
+    }
+    let (source_mark, target_mark) = g[edge].edge_type().marks();
+    match g.edge_endpoints(edge) {
+        Some((_, target)) if target == node => target_mark == dagitty::Mark::Arrow,
+        Some(_) => source_mark == dagitty::Mark::Arrow,
+        None => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::graph::dagitty::EdgeType as Edge;
 
     #[test]
     fn test_ancestors() {
-        let mut g = Graph::<&str, &str>::new();
+        let mut g = StableGraph::<&str, Edge>::new();
 
         let a = g.add_node("A");
         let b = g.add_node("B");
@@ -157,18 +732,208 @@ mod tests {
         let e = g.add_node("E");
         let z = g.add_node("Z");
 
-        g.add_edge(a, e, "");
-        g.add_edge(a, z, "");
-        g.add_edge(b, d, "");
-        g.add_edge(b, z, "");
-        g.add_edge(e, d, "");
+        g.add_edge(a, e, Edge::Directed);
+        g.add_edge(a, z, Edge::Directed);
+        g.add_edge(b, d, Edge::Directed);
+        g.add_edge(b, z, Edge::Directed);
+        g.add_edge(e, d, Edge::Directed);
 
-        let mut iter = g.ancestors(a).into_iter();
+        let mut iter = g.ancestors(a);
 
         assert_eq!(None, iter.next());
 
-        let iter = g.ancestors(z).into_iter();
+        let iter = g.ancestors(z);
         let nodes = iter.collect::<HashSet<NodeIndex>>();
         assert_eq!(HashSet::from([a, b]), nodes);
+
+        let nodes = g.ancestors_of_set(&[z, d]).collect::<HashSet<NodeIndex>>();
+        assert_eq!(HashSet::from([a, b, e]), nodes);
+    }
+
+    #[test]
+    fn test_relatives() {
+        // A -> E -> D <- B -> Z <- A
+        let mut g = StableGraph::<&str, Edge>::new();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let d = g.add_node("D");
+        let e = g.add_node("E");
+        let z = g.add_node("Z");
+        g.add_edges(
+            [
+                (a, e, Edge::Directed),
+                (a, z, Edge::Directed),
+                (b, d, Edge::Directed),
+                (b, z, Edge::Directed),
+                (e, d, Edge::Directed),
+            ]
+            .into_iter(),
+        );
+
+        let nodes = g.descendants(a).collect::<HashSet<NodeIndex>>();
+        assert_eq!(HashSet::from([d, e, z]), nodes);
+        let nodes = g
+            .descendants_of_set(&[e, b])
+            .collect::<HashSet<NodeIndex>>();
+        assert_eq!(HashSet::from([d, z]), nodes);
+
+        assert_eq!(vec![a, b], g.parents(z));
+        assert_eq!(vec![e, z], g.children(a));
+        assert_eq!(vec![d, e, z], g.children_of_set(&[a, b]));
+        assert_eq!(vec![a, b], g.parents_of_set(&[d, e]));
+        assert_eq!(vec![b], g.spouses(a));
+        assert_eq!(vec![b], g.spouses(e));
+        assert_eq!(vec![a, e], g.spouses(b));
+        assert_eq!(vec![d, e, z], g.neighbours_of_set(&[a, b]));
+        assert_eq!(vec![a, b, d], g.markov_blanket(e));
+        assert_eq!(vec![b, e], g.markov_blanket_of_set(&[a, z]));
+    }
+
+    #[test]
+    fn test_relatives_undirected() {
+        // A - B - C   D
+        let mut g = StableGraph::<&str, Edge, Undirected>::default();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let c = g.add_node("C");
+        let d = g.add_node("D");
+        g.add_edges([(a, b, Edge::Undirected), (b, c, Edge::Undirected)].into_iter());
+
+        assert_eq!(vec![a, c], g.parents(b));
+        assert_eq!(vec![a, c], g.children(b));
+        assert_eq!(vec![a, c], g.neighbours(b));
+        assert_eq!(vec![b, c], g.markov_blanket(a));
+        assert!(g.markov_blanket(d).is_empty());
+        let nodes = g.ancestors(a).collect::<HashSet<NodeIndex>>();
+        assert_eq!(HashSet::from([b, c]), nodes);
+        let nodes = g.descendants(d).collect::<HashSet<NodeIndex>>();
+        assert!(nodes.is_empty());
+    }
+
+    #[test]
+    fn test_cycles() {
+        let mut g = StableGraph::<&str, Edge>::new();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let c = g.add_node("C");
+        let d = g.add_node("D");
+        g.add_edges(
+            [
+                (a, b, Edge::Directed),
+                (b, c, Edge::Directed),
+                (c, d, Edge::Directed),
+                (d, a, Edge::Bidirected),
+            ]
+            .into_iter(),
+        );
+
+        assert_eq!(None, g.find_cycle());
+        assert_eq!(Some(vec![d, b, c]), g.closes_cycle(d, b));
+        assert_eq!(Some(vec![a]), g.closes_cycle(a, a));
+        assert_eq!(None, g.closes_cycle(a, d));
+
+        g.add_edge(d, b, Edge::Directed);
+        assert_eq!(Some(vec![b, c, d]), g.find_cycle());
+
+        let mut g = StableUnGraph::<&str, Edge>::default();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        g.add_edge(a, b, Edge::Undirected);
+        assert_eq!(None, g.find_cycle());
+        assert_eq!(None, g.closes_cycle(a, b));
+    }
+
+    #[test]
+    fn test_causal_order() {
+        let mut g = StableGraph::<&str, Edge>::new();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let c = g.add_node("C");
+        let d = g.add_node("D");
+        let e = g.add_node("E");
+        g.add_edges(
+            [
+                (d, b, Edge::Directed),
+                (a, b, Edge::Directed),
+                (b, c, Edge::Directed),
+                (a, c, Edge::Directed),
+                (c, e, Edge::Bidirected),
+            ]
+            .into_iter(),
+        );
+
+        assert_eq!(
+            Ok(vec![vec![a, d, e], vec![b], vec![c]]),
+            g.causal_order_layers()
+        );
+        assert_eq!(Ok(vec![a, d, e, b, c]), g.topological_sort());
+        assert!(g.is_ancestral_set(&[a, b, d]));
+        assert!(g.is_ancestral_set(&[e]));
+        assert!(!g.is_ancestral_set(&[a, c]));
+
+        g.add_edge(c, d, Edge::Directed);
+        assert_eq!(Err(vec![c, d, b]), g.topological_sort());
+
+        let mut g = StableUnGraph::<&str, Edge>::default();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        g.add_edge(a, b, Edge::Undirected);
+        assert_eq!(Ok(vec![vec![a, b]]), g.causal_order_layers());
+    }
+
+    #[test]
+    fn test_find_and_remove_edges() {
+        let mut g: CausalGraph<&str, Edge> = GraphKind::Dag(StableGraph::new()).into();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let ab = g.add_edge(a, b, Edge::Directed);
+        assert_eq!(Some(ab), g.find_edge(a, b));
+        assert_eq!(None, g.find_edge(b, a));
+        assert_eq!(Some(Edge::Directed), g.remove_edge(ab));
+        assert_eq!(None, g.find_edge(a, b));
+
+        let mut g: CausalGraph<&str, Edge> = GraphKind::Ungraph(StableUnGraph::default()).into();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let ab = g.add_edge(a, b, Edge::Undirected);
+        assert_eq!(Some(ab), g.find_edge(b, a));
+        assert_eq!(Some(Edge::Undirected), g.remove_edge(ab));
+        assert!(g.edge_indices().is_empty());
+    }
+
+    #[test]
+    fn test_removals_keep_indices() {
+        // A -> B -> C -> D, A -> C, D <-> B
+        let mut g: CausalGraph<&str, Edge> = GraphKind::Dag(StableGraph::new()).into();
+        let nodes: Vec<NodeIndex> = ["A", "B", "C", "D"]
+            .iter()
+            .map(|n| g.add_node(*n))
+            .collect();
+        let (a, b, c, d) = (nodes[0], nodes[1], nodes[2], nodes[3]);
+        let ab = g.add_edge(a, b, Edge::Directed);
+        let bc = g.add_edge(b, c, Edge::Directed);
+        let cd = g.add_edge(c, d, Edge::Directed);
+        let ac = g.add_edge(a, c, Edge::Directed);
+        let db = g.add_edge(d, b, Edge::Bidirected);
+
+        assert_eq!(vec![ab, bc, db], g.edges(b));
+        assert_eq!(vec![cd, db], g.edges(d));
+
+        assert_eq!(Some("B"), g.remove_node(b));
+        assert_eq!(vec![a, c, d], g.node_indices());
+        assert_eq!(vec![cd, ac], g.edge_indices());
+        assert_eq!(Some(&"D"), g.node_weight(d));
+        assert_eq!(Some((c, d)), g.edge_endpoints(cd));
+        assert_eq!(Some((a, c)), g.edge_endpoints(ac));
+
+        assert_eq!(Some(Edge::Directed), g.remove_edge(cd));
+        assert_eq!(vec![ac], g.edge_indices());
+        assert_eq!(Some((a, c)), g.edge_endpoints(ac));
+        assert_eq!(None, g.edge_weight(cd));
+
+        let e = g.add_node("E");
+        assert_eq!(Some(&"E"), g.node_weight(e));
+        assert_eq!(Some(&"C"), g.node_weight(c));
+        assert_eq!(Some(&Edge::Directed), g.edge_weight(ac));
     }
 }
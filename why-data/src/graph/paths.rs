@@ -0,0 +1,268 @@
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+use petgraph::Direction::Outgoing;
+
+use super::dagitty::EdgeType;
+use super::separation::ancestral_set;
+use super::{
+    directed_neighbors, has_arrowhead, incident_edges, CausalEdge, DefaultIx, Directed, EdgeIndex,
+    Incoming, IndexType, NodeIndex, StableGraph,
+};
+
+/// A path between exposures and outcomes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CausalPath<Ix = DefaultIx> {
+    /// Nodes along the path, starting at an exposure and ending at an outcome.
+    pub nodes: Vec<NodeIndex<Ix>>,
+    /// Edges along the path.
+    pub edges: Vec<EdgeIndex<Ix>>,
+    /// True if every edge points away from the exposure.
+    pub causal: bool,
+    /// True if the path is d-connecting given the conditioned nodes.
+    pub open: bool,
+}
+
+/// Paths is an iterator over all proper paths between two node sets, following
+/// edges in both directions.
+///
+/// Paths never go back through a node of the starting set and stop at the first
+/// node of the ending set. Parallel edges between two nodes give different paths.
+pub struct Paths<'a, N, E, Ix>
+where
+    Ix: IndexType,
+{
+    g: &'a StableGraph<N, E, Directed, Ix>,
+    from: HashSet<NodeIndex<Ix>>,
+    to: HashSet<NodeIndex<Ix>>,
+    conditioned: HashSet<NodeIndex<Ix>>,
+    conditioned_ancestors: HashSet<NodeIndex<Ix>>,
+    sources: Vec<NodeIndex<Ix>>,
+    path: Vec<NodeIndex<Ix>>,
+    path_edges: Vec<EdgeIndex<Ix>>,
+    pending_neighbors: Vec<Vec<(EdgeIndex<Ix>, NodeIndex<Ix>)>>,
+}
+
+impl<'a, N, E: CausalEdge, Ix: IndexType> Paths<'a, N, E, Ix> {
+    /// Create an iterator over the paths from `from` to `to`, classified given `conditioned`.
+    pub fn new(
+        g: &'a StableGraph<N, E, Directed, Ix>,
+        from: &[NodeIndex<Ix>],
+        to: &[NodeIndex<Ix>],
+        conditioned: &[NodeIndex<Ix>],
+    ) -> Self {
+        let conditioned: HashSet<NodeIndex<Ix>> = conditioned.iter().copied().collect();
+        let mut sources: Vec<NodeIndex<Ix>> = from.to_vec();
+        sources.sort();
+        sources.dedup();
+        sources.reverse();
+        Self {
+            g,
+            from: from.iter().copied().collect(),
+            to: to.iter().copied().collect(),
+            conditioned_ancestors: ancestral_set(&conditioned, |n| {
+                directed_neighbors(g, n, Incoming)
+            }),
+            conditioned,
+            sources,
+            path: vec![],
+            path_edges: vec![],
+            pending_neighbors: vec![],
+        }
+    }
+
+    fn neighbors(&self, node: NodeIndex<Ix>) -> Vec<(EdgeIndex<Ix>, NodeIndex<Ix>)> {
+        let mut next = incident_edges(self.g, node);
+        next.sort_by_key(|(e, n)| (*n, *e));
+        next.reverse();
+        next
+    }
+
+    fn causal_path(&self) -> CausalPath<Ix> {
+        CausalPath {
+            nodes: self.path.clone(),
+            edges: self.path_edges.clone(),
+            causal: is_causal(self.g, &self.path, &self.path_edges),
+            open: is_open(
+                self.g,
+                &self.path,
+                &self.path_edges,
+                &self.conditioned,
+                &self.conditioned_ancestors,
+            ),
+        }
+    }
+}
+
+impl<'a, N, E, Ix: IndexType> Debug for Paths<'a, N, E, Ix> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Paths current: {:?}", self.path)
+    }
+}
+
+impl<'a, N, E: CausalEdge, Ix: IndexType> Iterator for Paths<'a, N, E, Ix> {
+    type Item = CausalPath<Ix>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = match self.pending_neighbors.last_mut() {
+                None => {
+                    let source = self.sources.pop()?;
+                    self.path = vec![source];
+                    self.path_edges = vec![];
+                    self.pending_neighbors.push(self.neighbors(source));
+                    continue;
+                }
+                Some(neighbors) => neighbors.pop(),
+            };
+
+            match node {
+                None => {
+                    self.pending_neighbors.pop();
+                    self.path.pop();
+                    self.path_edges.pop();
+                }
+                Some((_, node)) if self.from.contains(&node) || self.path.contains(&node) => {}
+                Some((edge, node)) => {
+                    self.path.push(node);
+                    self.path_edges.push(edge);
+                    if self.to.contains(&node) {
+                        let path = self.causal_path();
+                        self.path.pop();
+                        self.path_edges.pop();
+                        return Some(path);
+                    }
+                    self.pending_neighbors.push(self.neighbors(node));
+                }
+            }
+        }
+    }
+}
+
+/// Return true if every edge of the path is directed away from its first node.
+pub(crate) fn is_causal<N, E: CausalEdge, Ix: IndexType>(
+    g: &StableGraph<N, E, Directed, Ix>,
+    path: &[NodeIndex<Ix>],
+    edges: &[EdgeIndex<Ix>],
+) -> bool {
+    path.iter().zip(edges).all(|(node, edge)| {
+        g[*edge].edge_type() == EdgeType::Directed
+            && g.edge_endpoints(*edge).map(|(s, _)| s) == Some(*node)
+    })
+}
+
+/// Return true if the path is d-connecting given `z`.
+///
+/// `z_ancestors` must contain `z` and all its ancestors.
+pub(crate) fn is_open<N, E: CausalEdge, Ix: IndexType>(
+    g: &StableGraph<N, E, Directed, Ix>,
+    path: &[NodeIndex<Ix>],
+    edges: &[EdgeIndex<Ix>],
+    z: &HashSet<NodeIndex<Ix>>,
+    z_ancestors: &HashSet<NodeIndex<Ix>>,
+) -> bool {
+    (1..path.len().saturating_sub(1)).all(|i| {
+        let node = path[i];
+        let collider = has_arrowhead(g, edges[i - 1], node) && has_arrowhead(g, edges[i], node);
+        if collider {
+            z_ancestors.contains(&node)
+        } else {
+            !z.contains(&node)
+        }
+    })
+}
+
+/// Return `nodes` together with all their descendants.
+pub(crate) fn descendant_set<N, E: CausalEdge, Ix: IndexType>(
+    g: &StableGraph<N, E, Directed, Ix>,
+    nodes: &HashSet<NodeIndex<Ix>>,
+) -> HashSet<NodeIndex<Ix>> {
+    let mut result = nodes.clone();
+    let mut pending: Vec<NodeIndex<Ix>> = nodes.iter().copied().collect();
+    while let Some(node) = pending.pop() {
+        for child in directed_neighbors(g, node, Outgoing) {
+            if result.insert(child) {
+                pending.push(child);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::dagitty::EdgeType as Edge;
+
+    #[test]
+    fn test_paths() {
+        // E <- A -> Z <- B -> D, E -> D
+        let mut g = StableGraph::<&str, Edge>::new();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let d = g.add_node("D");
+        let e = g.add_node("E");
+        let z = g.add_node("Z");
+        g.add_edge(a, e, Edge::Directed);
+        g.add_edge(a, z, Edge::Directed);
+        g.add_edge(b, d, Edge::Directed);
+        g.add_edge(b, z, Edge::Directed);
+        g.add_edge(e, d, Edge::Directed);
+
+        let paths: Vec<CausalPath> = Paths::new(&g, &[e], &[d], &[]).collect();
+        assert_eq!(2, paths.len());
+        assert_eq!(vec![e, a, z, b, d], paths[0].nodes);
+        assert_eq!(4, paths[0].edges.len());
+        assert!(!paths[0].causal);
+        assert!(!paths[0].open);
+        assert_eq!(vec![e, d], paths[1].nodes);
+        assert!(paths[1].causal);
+        assert!(paths[1].open);
+
+        let paths: Vec<CausalPath> = Paths::new(&g, &[e], &[d], &[z]).collect();
+        assert!(paths[0].open);
+        let paths: Vec<CausalPath> = Paths::new(&g, &[e], &[d], &[z, a]).collect();
+        assert!(!paths[0].open);
+
+        let paths: Vec<CausalPath> = Paths::new(&g, &[a, e], &[d], &[]).collect();
+        assert_eq!(
+            vec![vec![a, z, b, d], vec![e, d]],
+            paths.into_iter().map(|p| p.nodes).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            HashSet::from([e, d]),
+            descendant_set(&g, &HashSet::from([e]))
+        );
+    }
+
+    #[test]
+    fn test_paths_bidirected() {
+        // X -> Y, X <-> Y, X <-> M -> Y
+        let mut g = StableGraph::<&str, Edge>::new();
+        let x = g.add_node("X");
+        let m = g.add_node("M");
+        let y = g.add_node("Y");
+        let direct = g.add_edge(x, y, Edge::Directed);
+        let confounded = g.add_edge(x, y, Edge::Bidirected);
+        g.add_edge(x, m, Edge::Bidirected);
+        g.add_edge(m, y, Edge::Directed);
+
+        let paths: Vec<CausalPath> = Paths::new(&g, &[x], &[y], &[]).collect();
+        assert_eq!(3, paths.len());
+        assert_eq!(vec![x, m, y], paths[0].nodes);
+        assert!(!paths[0].causal);
+        assert!(paths[0].open);
+        assert_eq!(vec![direct], paths[1].edges);
+        assert!(paths[1].causal);
+        assert_eq!(vec![confounded], paths[2].edges);
+        assert!(!paths[2].causal);
+        assert!(paths[2].open);
+
+        let paths: Vec<CausalPath> = Paths::new(&g, &[x], &[y], &[m]).collect();
+        assert!(!paths[0].open);
+        assert!(
+            HashSet::from([m])
+                == ancestral_set(&HashSet::from([m]), |n| directed_neighbors(&g, n, Incoming))
+        );
+    }
+}
@@ -0,0 +1,384 @@
+//! The JSON form of a model is
+//!
+//! ```json
+//! {
+//!   "kind": "dag",
+//!   "name": null,
+//!   "strict": false,
+//!   "attributes": { "bb": "0,0,1,1" },
+//!   "nodes": [
+//!     { "id": "E", "type": "exposure", "pos": { "x": 0.0, "y": 1.0 }, "attributes": {} },
+//!     { "id": "Y", "type": "outcome", "pos": { "x": 1.0, "y": 1.0 }, "attributes": { "label": "y" } }
+//!   ],
+//!   "edges": [
+//!     { "source": "E", "target": "Y", "type": "directed", "pos": null, "attributes": {} }
+//!   ]
+//! }
+//! ```
+//!
+//! `kind` is one of `dag`, `graph`, `pdag`, `mag` and `pag`. Node `type` is
+//! `none` or a dagitty vertex type, and edge `type` one of `directed`,
+//! `bidirected`, `undirected`, `partially_directed`, `nondirected` and
+//! `partially_undirected`. Edges refer to nodes by id. Names, ids and attribute
+//! values are plain strings, quoted again where dagitty needs it when reading, and
+//! flags have an empty value. Every field but `id`, `source`, `target` and
+//! `nodes` may be left out when reading.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use serde::de::{self, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::dagitty::{identifier, name, EdgeInfo, EdgeType, NodeInfo, VertexType};
+use super::{CausalEdge, CausalGraph, GraphKind, StableDiGraph, StableUnGraph};
+use crate::types::Point;
+
+/// Dagitty form of a plain attribute value, leaving flags empty.
+fn attribute_value(value: &str) -> String {
+    if value.is_empty() {
+        String::new()
+    } else {
+        identifier(value)
+    }
+}
+
+/// Attributes as a JSON object of plain values, keeping their order.
+mod attributes {
+    use super::*;
+
+    pub(super) fn serialize<S: Serializer>(
+        attributes: &[(String, String)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(attributes.len()))?;
+        for (key, value) in attributes {
+            map.serialize_entry(key, &name(value))?;
+        }
+        map.end()
+    }
+
+    struct AttributesVisitor;
+
+    impl<'de> Visitor<'de> for AttributesVisitor {
+        type Value = Vec<(String, String)>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a map of attribute values")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut attributes = Vec::new();
+            while let Some((key, value)) = map.next_entry::<String, String>()? {
+                attributes.push((key, attribute_value(&value)));
+            }
+            Ok(attributes)
+        }
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, String)>, D::Error> {
+        deserializer.deserialize_map(AttributesVisitor)
+    }
+}
+
+fn default_vertex_type() -> VertexType {
+    VertexType::None
+}
+
+fn default_edge_type() -> EdgeType {
+    EdgeType::Directed
+}
+
+fn origin() -> Point<f64> {
+    Point::new(0.0, 0.0)
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    Dag,
+    Graph,
+    Pdag,
+    Mag,
+    Pag,
+}
+
+#[derive(Serialize, Deserialize)]
+struct NodeData {
+    id: String,
+    #[serde(rename = "type", default = "default_vertex_type")]
+    vertex_type: VertexType,
+    #[serde(default = "origin")]
+    pos: Point<f64>,
+    #[serde(default, with = "attributes")]
+    attributes: Vec<(String, String)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EdgeInfoData {
+    #[serde(rename = "type", default = "default_edge_type")]
+    edge_type: EdgeType,
+    #[serde(default)]
+    pos: Option<Point<f64>>,
+    #[serde(default, with = "attributes")]
+    attributes: Vec<(String, String)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EdgeData {
+    source: String,
+    target: String,
+    #[serde(flatten)]
+    edge: EdgeInfoData,
+}
+
+#[derive(Serialize, Deserialize)]
+struct GraphData {
+    kind: Kind,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    strict: bool,
+    #[serde(default)]
+    attributes: BTreeMap<String, String>,
+    nodes: Vec<NodeData>,
+    #[serde(default)]
+    edges: Vec<EdgeData>,
+}
+
+impl From<&NodeInfo> for NodeData {
+    fn from(node: &NodeInfo) -> Self {
+        NodeData {
+            id: name(&node.id.lock_ref()),
+            vertex_type: node.vertex_type.get(),
+            pos: node.layout_pos.get(),
+            attributes: node.attributes.clone(),
+        }
+    }
+}
+
+impl From<NodeData> for NodeInfo {
+    fn from(data: NodeData) -> Self {
+        let mut node = NodeInfo::new(
+            &identifier(&data.id),
+            data.pos.x(),
+            data.pos.y(),
+            data.vertex_type,
+        );
+        node.attributes = data.attributes;
+        node
+    }
+}
+
+impl Serialize for NodeInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        NodeData::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeInfo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        NodeData::deserialize(deserializer).map(NodeInfo::from)
+    }
+}
+
+impl From<&EdgeInfo> for EdgeInfoData {
+    fn from(edge: &EdgeInfo) -> Self {
+        EdgeInfoData {
+            edge_type: edge.edge_type(),
+            pos: edge.layout_pos.get(),
+            attributes: edge.attributes.clone(),
+        }
+    }
+}
+
+impl From<EdgeInfoData> for EdgeInfo {
+    fn from(data: EdgeInfoData) -> Self {
+        let mut edge = EdgeInfo::new("", data.pos, data.edge_type);
+        edge.attributes = data.attributes;
+        edge
+    }
+}
+
+impl Serialize for EdgeInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        EdgeInfoData::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for EdgeInfo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        EdgeInfoData::deserialize(deserializer).map(EdgeInfo::from)
+    }
+}
+
+impl Serialize for CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let kind = match self.kind {
+            GraphKind::Dag(_) => Kind::Dag,
+            GraphKind::Ungraph(_) => Kind::Graph,
+            GraphKind::Pdag(_) => Kind::Pdag,
+            GraphKind::Mag(_) => Kind::Mag,
+            GraphKind::Pag(_) => Kind::Pag,
+        };
+        let nodes = self
+            .node_indices()
+            .into_iter()
+            .map(|n| NodeData::from(&**self.node_weight(n).unwrap()))
+            .collect();
+        let edges = self
+            .edge_indices()
+            .into_iter()
+            .map(|e| {
+                let (source, target) = self.edge_endpoints(e).unwrap();
+                EdgeData {
+                    source: name(&self.node_weight(source).unwrap().id.lock_ref()),
+                    target: name(&self.node_weight(target).unwrap().id.lock_ref()),
+                    edge: EdgeInfoData::from(&**self.edge_weight(e).unwrap()),
+                }
+            })
+            .collect();
+        GraphData {
+            kind,
+            name: self.name.as_deref().map(name),
+            strict: self.strict,
+            attributes: self
+                .attributes
+                .iter()
+                .map(|(key, value)| (key.clone(), name(value)))
+                .collect(),
+            nodes,
+            edges,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = GraphData::deserialize(deserializer)?;
+        let mut graph: CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>> = match data.kind {
+            Kind::Dag => GraphKind::Dag(StableDiGraph::new()),
+            Kind::Graph => GraphKind::Ungraph(StableUnGraph::default()),
+            Kind::Pdag => GraphKind::Pdag(StableDiGraph::new()),
+            Kind::Mag => GraphKind::Mag(StableDiGraph::new()),
+            Kind::Pag => GraphKind::Pag(StableDiGraph::new()),
+        }
+        .into();
+        graph.name = data.name.as_deref().map(identifier);
+        graph.strict = data.strict;
+        graph.attributes = data
+            .attributes
+            .iter()
+            .map(|(key, value)| (key.clone(), attribute_value(value)))
+            .collect();
+
+        let mut node_map = HashMap::new();
+        for node in data.nodes {
+            let id = node.id.clone();
+            let index = graph.add_node(Arc::new(node.into()));
+            if node_map.insert(id.clone(), index).is_some() {
+                return Err(de::Error::custom(format!("duplicate node {}", id)));
+            }
+        }
+        for edge in data.edges {
+            let endpoint = |id: &str| {
+                node_map
+                    .get(id)
+                    .copied()
+                    .ok_or_else(|| de::Error::custom(format!("unknown node {}", id)))
+            };
+            let (source, target) = (endpoint(&edge.source)?, endpoint(&edge.target)?);
+            graph.add_edge(source, target, Arc::new(edge.edge.into()));
+        }
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>> {
+        let mut g = StableDiGraph::new();
+        let mut e = NodeInfo::new("E", 0.0, 1.0, VertexType::Exposure);
+        e.attributes = vec![
+            ("label".into(), "\"my label\"".into()),
+            ("flag".into(), "".into()),
+        ];
+        let e = g.add_node(Arc::new(e));
+        let y = g.add_node(Arc::new(NodeInfo::new(
+            "\"Y 1\"",
+            1.0,
+            1.0,
+            VertexType::Outcome,
+        )));
+        let pos = Some(Point::new(0.5, 0.5));
+        g.add_edge(e, y, Arc::new(EdgeInfo::new("", pos, EdgeType::Bidirected)));
+        let mut graph: CausalGraph<_, _> = GraphKind::Mag(g).into();
+        graph.name = Some("m".into());
+        graph.attributes.insert("bb".into(), "\"0,0,1,1\"".into());
+        graph
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let json = serde_json::to_value(graph()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "mag",
+                "name": "m",
+                "strict": false,
+                "attributes": { "bb": "0,0,1,1" },
+                "nodes": [
+                    {
+                        "id": "E",
+                        "type": "exposure",
+                        "pos": { "x": 0.0, "y": 1.0 },
+                        "attributes": { "label": "my label", "flag": "" }
+                    },
+                    {
+                        "id": "Y 1",
+                        "type": "outcome",
+                        "pos": { "x": 1.0, "y": 1.0 },
+                        "attributes": {}
+                    }
+                ],
+                "edges": [
+                    {
+                        "source": "E",
+                        "target": "Y 1",
+                        "type": "bidirected",
+                        "pos": { "x": 0.5, "y": 0.5 },
+                        "attributes": {}
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let graph = graph();
+        let json = serde_json::to_string(&graph).unwrap();
+        let parsed: CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>> =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, graph);
+
+        let minimal: CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>> = serde_json::from_str(
+            r#"{"kind": "dag", "nodes": [{"id": "A"}, {"id": "B"}], "edges": [{"source": "A", "target": "B"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(minimal.edge_indices().len(), 1);
+
+        let unknown = serde_json::from_str::<CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>>(
+            r#"{"kind": "dag", "nodes": [{"id": "A"}], "edges": [{"source": "A", "target": "B"}]}"#,
+        );
+        assert!(unknown.is_err());
+    }
+}
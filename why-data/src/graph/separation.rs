@@ -0,0 +1,377 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use super::{
+    directed_neighbors, has_arrowhead, incident_edges, CausalEdge, EdgeType, Incoming, IndexType,
+    NodeIndex, StableGraph,
+};
+
+/// Reachability over edge marks: returns true if `x` and `y` are d-separated given `z`.
+///
+/// A node is a collider when both edges meeting there carry an arrowhead at it, which
+/// makes this m-separation as soon as the graph has bidirected edges. Undirected graphs
+/// have no colliders, so d-separation reduces to plain vertex separation by `z`.
+pub(crate) fn d_separated<N, E, Ty, Ix>(
+    g: &StableGraph<N, E, Ty, Ix>,
+    x: &[NodeIndex<Ix>],
+    y: &[NodeIndex<Ix>],
+    z: &[NodeIndex<Ix>],
+) -> bool
+where
+    E: CausalEdge,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let y: HashSet<NodeIndex<Ix>> = y.iter().copied().collect();
+    let z: HashSet<NodeIndex<Ix>> = z.iter().copied().collect();
+
+    if x.iter().any(|n| y.contains(n) && !z.contains(n)) {
+        return false;
+    }
+
+    // Colliders are open when they are conditioned on or have a conditioned descendant.
+    let z_ancestors = ancestral_set(&z, |n| directed_neighbors(g, n, Incoming));
+
+    // Nodes are visited together with whether the edge used to reach them has an
+    // arrowhead pointing at them.
+    let mut visited = HashSet::new();
+    let mut pending: VecDeque<(NodeIndex<Ix>, bool)> = x.iter().map(|n| (*n, false)).collect();
+
+    while let Some((node, arrowhead)) = pending.pop_front() {
+        if !visited.insert((node, arrowhead)) {
+            continue;
+        }
+        if !z.contains(&node) && y.contains(&node) {
+            return false;
+        }
+
+        for (edge, next) in incident_edges(g, node) {
+            let open = if arrowhead && has_arrowhead(g, edge, node) {
+                z_ancestors.contains(&node)
+            } else {
+                !z.contains(&node)
+            };
+            if open {
+                pending.push_back((next, has_arrowhead(g, edge, next)));
+            }
+        }
+    }
+
+    true
+}
+
+/// Undirected adjacency lists, used for moral graphs.
+pub(crate) type Adjacency<Ix> = HashMap<NodeIndex<Ix>, HashSet<NodeIndex<Ix>>>;
+
+/// Return `nodes` together with all their ancestors, following `parents`.
+pub(crate) fn ancestral_set<Ix, P>(
+    nodes: &HashSet<NodeIndex<Ix>>,
+    parents: P,
+) -> HashSet<NodeIndex<Ix>>
+where
+    Ix: IndexType,
+    P: Fn(NodeIndex<Ix>) -> Vec<NodeIndex<Ix>>,
+{
+    let mut result = nodes.clone();
+    let mut pending: Vec<NodeIndex<Ix>> = nodes.iter().copied().collect();
+    while let Some(node) = pending.pop() {
+        for parent in parents(node) {
+            if result.insert(parent) {
+                pending.push(parent);
+            }
+        }
+    }
+    result
+}
+
+/// Moral graph of the subgraph induced by `nodes`, which must be an ancestral set.
+///
+/// Nodes joined by `siblings` (bidirected edges) form districts, and every district
+/// is married together with its parents, so that collider paths become edges.
+pub(crate) fn moral_graph<Ix, P, S>(
+    nodes: &HashSet<NodeIndex<Ix>>,
+    parents: P,
+    siblings: S,
+) -> Adjacency<Ix>
+where
+    Ix: IndexType,
+    P: Fn(NodeIndex<Ix>) -> Vec<NodeIndex<Ix>>,
+    S: Fn(NodeIndex<Ix>) -> Vec<NodeIndex<Ix>>,
+{
+    let mut sibling_adj: Adjacency<Ix> = nodes.iter().map(|n| (*n, HashSet::new())).collect();
+    for node in nodes.iter() {
+        for sibling in siblings(*node).into_iter().filter(|s| nodes.contains(s)) {
+            sibling_adj.get_mut(node).unwrap().insert(sibling);
+            sibling_adj.get_mut(&sibling).unwrap().insert(*node);
+        }
+    }
+
+    let mut adj = sibling_adj.clone();
+    let mut assigned = HashSet::new();
+    for node in nodes.iter() {
+        if assigned.contains(node) {
+            continue;
+        }
+        let district = component(&sibling_adj, &HashSet::from([*node]), &HashSet::new());
+        assigned.extend(district.iter().copied());
+
+        let mut family: Vec<NodeIndex<Ix>> = district.iter().copied().collect();
+        for member in district.iter() {
+            family.extend(parents(*member).into_iter().filter(|p| nodes.contains(p)));
+        }
+        family.sort();
+        family.dedup();
+        for (i, p) in family.iter().enumerate() {
+            for q in family.iter().skip(i + 1) {
+                adj.get_mut(p).unwrap().insert(*q);
+                adj.get_mut(q).unwrap().insert(*p);
+            }
+        }
+    }
+    adj
+}
+
+/// Nodes reachable from `start` without entering `blocked`.
+fn component<Ix: IndexType>(
+    adj: &Adjacency<Ix>,
+    start: &HashSet<NodeIndex<Ix>>,
+    blocked: &HashSet<NodeIndex<Ix>>,
+) -> HashSet<NodeIndex<Ix>> {
+    let mut result: HashSet<NodeIndex<Ix>> = start
+        .iter()
+        .filter(|n| !blocked.contains(n))
+        .copied()
+        .collect();
+    let mut pending: Vec<NodeIndex<Ix>> = result.iter().copied().collect();
+    while let Some(node) = pending.pop() {
+        for n in adj.get(&node).into_iter().flatten() {
+            if !blocked.contains(n) && result.insert(*n) {
+                pending.push(*n);
+            }
+        }
+    }
+    result
+}
+
+/// Nodes adjacent to `nodes` but not part of it.
+fn neighbourhood<Ix: IndexType>(
+    adj: &Adjacency<Ix>,
+    nodes: &HashSet<NodeIndex<Ix>>,
+) -> HashSet<NodeIndex<Ix>> {
+    nodes
+        .iter()
+        .flat_map(|n| adj.get(n).into_iter().flatten())
+        .filter(|n| !nodes.contains(n))
+        .copied()
+        .collect()
+}
+
+/// Return true if `z` separates `x` from `y` in the undirected graph `adj`.
+pub(crate) fn separated<Ix: IndexType>(
+    adj: &Adjacency<Ix>,
+    x: &HashSet<NodeIndex<Ix>>,
+    y: &HashSet<NodeIndex<Ix>>,
+    z: &HashSet<NodeIndex<Ix>>,
+) -> bool {
+    component(adj, x, z).is_disjoint(y)
+}
+
+/// List all minimal sets `S ⊆ allowed` separating `x` from `y` in `adj`.
+///
+/// Backtracking over the separator closest to the `x` side: every node of that
+/// separator either belongs to the final separator or joins the `x` side.
+pub(crate) fn minimal_separators<Ix: IndexType>(
+    adj: &Adjacency<Ix>,
+    x: &HashSet<NodeIndex<Ix>>,
+    y: &HashSet<NodeIndex<Ix>>,
+    allowed: &HashSet<NodeIndex<Ix>>,
+) -> Vec<Vec<NodeIndex<Ix>>> {
+    let mut result = BTreeSet::new();
+    if x.is_disjoint(y) {
+        list_minimal_separators(adj, x.clone(), HashSet::new(), y, allowed, &mut result);
+    }
+    result.into_iter().collect()
+}
+
+fn list_minimal_separators<Ix: IndexType>(
+    adj: &Adjacency<Ix>,
+    mut side: HashSet<NodeIndex<Ix>>,
+    included: HashSet<NodeIndex<Ix>>,
+    y: &HashSet<NodeIndex<Ix>>,
+    allowed: &HashSet<NodeIndex<Ix>>,
+    result: &mut BTreeSet<Vec<NodeIndex<Ix>>>,
+) {
+    let separator = loop {
+        let boundary = neighbourhood(adj, &side);
+        if !boundary.is_disjoint(y) {
+            return;
+        }
+        let y_side = component(adj, y, &boundary);
+        let separator = neighbourhood(adj, &y_side);
+        let forced: Vec<NodeIndex<Ix>> = separator
+            .iter()
+            .filter(|n| !allowed.contains(n))
+            .copied()
+            .collect();
+        if forced.is_empty() {
+            break separator;
+        }
+        side = component(adj, &side, &separator);
+        side.extend(forced);
+    };
+
+    if !included.is_subset(&separator) {
+        return;
+    }
+
+    let mut candidates: Vec<NodeIndex<Ix>> = separator.difference(&included).copied().collect();
+    candidates.sort();
+    match candidates.first() {
+        None => {
+            let mut separator: Vec<NodeIndex<Ix>> = separator.into_iter().collect();
+            separator.sort();
+            result.insert(separator);
+        }
+        Some(node) => {
+            let side = component(adj, &side, &separator);
+
+            let mut outside = side.clone();
+            outside.insert(*node);
+            list_minimal_separators(adj, outside, included.clone(), y, allowed, result);
+
+            let mut inside = included;
+            inside.insert(*node);
+            list_minimal_separators(adj, side, inside, y, allowed, result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::dagitty::EdgeType as Edge;
+    use crate::graph::{CausalGraphExt, StableUnGraph};
+
+    #[test]
+    fn test_chain() {
+        let mut g = StableGraph::<&str, Edge>::new();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let c = g.add_node("C");
+        g.add_edge(a, b, Edge::Directed);
+        g.add_edge(b, c, Edge::Directed);
+
+        assert!(!g.d_separated(&[a], &[c], &[]));
+        assert!(g.d_separated(&[a], &[c], &[b]));
+    }
+
+    #[test]
+    fn test_fork() {
+        let mut g = StableGraph::<&str, Edge>::new();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let c = g.add_node("C");
+        g.add_edge(b, a, Edge::Directed);
+        g.add_edge(b, c, Edge::Directed);
+
+        assert!(!g.d_separated(&[a], &[c], &[]));
+        assert!(g.d_separated(&[a], &[c], &[b]));
+    }
+
+    #[test]
+    fn test_collider() {
+        let mut g = StableGraph::<&str, Edge>::new();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let c = g.add_node("C");
+        let d = g.add_node("D");
+        g.add_edge(a, b, Edge::Directed);
+        g.add_edge(c, b, Edge::Directed);
+        g.add_edge(b, d, Edge::Directed);
+
+        assert!(g.d_separated(&[a], &[c], &[]));
+        assert!(!g.d_separated(&[a], &[c], &[b]));
+        assert!(!g.d_separated(&[a], &[c], &[d]));
+    }
+
+    #[test]
+    fn test_m_bias() {
+        // E <- A -> Z <- B -> D, E -> D
+        let mut g = StableGraph::<&str, Edge>::new();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let d = g.add_node("D");
+        let e = g.add_node("E");
+        let z = g.add_node("Z");
+        g.add_edge(a, e, Edge::Directed);
+        g.add_edge(a, z, Edge::Directed);
+        g.add_edge(b, d, Edge::Directed);
+        g.add_edge(b, z, Edge::Directed);
+        g.add_edge(e, d, Edge::Directed);
+
+        assert!(g.d_separated(&[a], &[b], &[]));
+        assert!(!g.d_separated(&[a], &[b], &[z]));
+        assert!(g.d_separated(&[e], &[b], &[z, a]));
+        assert!(g.d_separated(&[e], &[b], &[]));
+        assert!(!g.d_separated(&[e], &[b], &[z]));
+        assert!(!g.d_separated(&[e], &[d], &[]));
+        assert!(g.d_separated(&[a, e], &[b], &[]));
+        assert!(!g.d_separated(&[a, e], &[b, d], &[]));
+    }
+
+    #[test]
+    fn test_bidirected() {
+        // A -> B <-> C -> D
+        let mut g = StableGraph::<&str, Edge>::new();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let c = g.add_node("C");
+        let d = g.add_node("D");
+        g.add_edge(a, b, Edge::Directed);
+        g.add_edge(b, c, Edge::Bidirected);
+        g.add_edge(c, d, Edge::Directed);
+
+        assert!(!g.d_separated(&[b], &[c], &[]));
+        assert!(!g.d_separated(&[b], &[d], &[]));
+        assert!(g.d_separated(&[b], &[d], &[c]));
+        // B is a collider between A and C, and C is no descendant of B.
+        assert!(g.d_separated(&[a], &[c], &[]));
+        assert!(g.d_separated(&[a], &[d], &[]));
+        assert!(!g.d_separated(&[a], &[c], &[b]));
+        assert!(!g.d_separated(&[a], &[d], &[b]));
+        assert!(g.d_separated(&[a], &[d], &[b, c]));
+        assert_eq!(0, g.ancestors(c).count());
+    }
+
+    #[test]
+    fn test_circle_marks() {
+        // Only arrowheads make colliders: A @-> B <-@ C but A @-@ D @-@ C.
+        let mut g = StableGraph::<&str, Edge>::new();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let c = g.add_node("C");
+        let d = g.add_node("D");
+        g.add_edge(a, b, Edge::PartiallyDirected);
+        g.add_edge(c, b, Edge::PartiallyDirected);
+        assert!(g.d_separated(&[a], &[c], &[]));
+        assert!(!g.d_separated(&[a], &[c], &[b]));
+
+        g.add_edge(a, d, Edge::Nondirected);
+        g.add_edge(d, c, Edge::Nondirected);
+        assert!(!g.d_separated(&[a], &[c], &[]));
+        assert!(g.d_separated(&[a], &[c], &[d]));
+        assert!(g.parents(b).is_empty());
+    }
+
+    #[test]
+    fn test_undirected_separation() {
+        let mut g = StableUnGraph::<&str, Edge>::default();
+        let a = g.add_node("A");
+        let b = g.add_node("B");
+        let c = g.add_node("C");
+        g.add_edge(a, b, Edge::Undirected);
+        g.add_edge(c, b, Edge::Undirected);
+
+        assert!(!g.d_separated(&[a], &[c], &[]));
+        assert!(g.d_separated(&[a], &[c], &[b]));
+    }
+}
@@ -10,5 +10,9 @@
 
 /// Causal graph support
 pub mod graph;
+/// Values of the model that change after parsing.
+pub mod mutable;
+/// Headless SVG drawings of causal graphs.
+pub mod svg;
 /// Different useful types
 pub mod types;
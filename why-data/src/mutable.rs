@@ -0,0 +1,97 @@
+//! Values of the model that change after parsing, like positions and types.
+//!
+//! With the `signals` feature this is the reactive `Mutable` of
+//! `futures-signals`, which the user interface listens to. Without it a plain
+//! shared value with the same `get`, `set` and `lock_*` methods is used, so the
+//! analysis builds without reactive or browser crates.
+
+#[cfg(feature = "signals")]
+pub use futures_signals::signal::Mutable;
+
+#[cfg(not(feature = "signals"))]
+pub use plain::Mutable;
+
+#[cfg(not(feature = "signals"))]
+mod plain {
+    use std::fmt::Debug;
+    use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+    /// Shared value, cloning it gives another handle to the same value.
+    pub struct Mutable<T>(Arc<RwLock<T>>);
+
+    impl<T> Mutable<T> {
+        /// Create a new value.
+        pub fn new(value: T) -> Self {
+            Self(Arc::new(RwLock::new(value)))
+        }
+
+        /// Lock the value for reading.
+        pub fn lock_ref(&self) -> RwLockReadGuard<'_, T> {
+            self.0.read().unwrap_or_else(|e| e.into_inner())
+        }
+
+        /// Lock the value for writing.
+        pub fn lock_mut(&self) -> RwLockWriteGuard<'_, T> {
+            self.0.write().unwrap_or_else(|e| e.into_inner())
+        }
+
+        /// Replace the value, returning the old one.
+        pub fn replace(&self, value: T) -> T {
+            std::mem::replace(&mut *self.lock_mut(), value)
+        }
+
+        /// Set the value.
+        pub fn set(&self, value: T) {
+            *self.lock_mut() = value;
+        }
+    }
+
+    impl<T: Copy> Mutable<T> {
+        /// Copy of the value.
+        pub fn get(&self) -> T {
+            *self.lock_ref()
+        }
+    }
+
+    impl<T: Clone> Mutable<T> {
+        /// Clone of the value.
+        pub fn get_cloned(&self) -> T {
+            self.lock_ref().clone()
+        }
+    }
+
+    impl<T> Clone for Mutable<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+
+    impl<T: Default> Default for Mutable<T> {
+        fn default() -> Self {
+            Self::new(T::default())
+        }
+    }
+
+    impl<T: Debug> Debug for Mutable<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_tuple("Mutable").field(&*self.lock_ref()).finish()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_shared_value() {
+            let value = Mutable::new(1);
+            let other = value.clone();
+            other.set(2);
+            assert_eq!(value.get(), 2);
+            assert_eq!(value.replace(3), 2);
+            *other.lock_mut() += 1;
+            assert_eq!(*value.lock_ref(), 4);
+            assert_eq!(format!("{:?}", value), "Mutable(4)");
+        }
+    }
+}
@@ -0,0 +1,519 @@
+//! Headless SVG drawings of causal graphs.
+//!
+//! The geometry matches the interactive graph: vertices are ellipses labelled
+//! below, edges are lines or quadratic curves through their layout position, and
+//! edge marks sit where the edge leaves the vertex ellipse.
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+use crate::graph::dagitty::{name, EdgeInfo, Mark, NodeInfo, VertexType};
+use crate::graph::{CausalEdge, CausalGraph, EdgeIndex};
+use crate::types::Point;
+
+/// Horizontal and vertical radius of the vertex ellipses, in pixels.
+pub const VERTEX_RADII: (f64, f64) = (20.0, 15.0);
+
+/// Vertex ellipse centred at the origin.
+pub const VERTEX_PATH: &str = "M 0 0 m 20, 0 a 20,15 0 1,1 -40,0 a 20,15 0 1,1 40,0";
+
+/// Arrowhead pointing to the origin along the positive x axis.
+pub const ARROW_PATH: &str = "M-1,0L15,5L15,-5Z";
+
+/// Circle mark touching the origin along the positive x axis.
+pub const CIRCLE_PATH: &str = "M0,0A4,4,0,1,0,8,0A4,4,0,1,0,0,0Z";
+
+/// Gap in pixels between a vertex ellipse and the edge marks drawn at it.
+pub const MARK_GAP: f64 = 5.0;
+
+const EDGE_STROKE_COLOR: &str = "black";
+const EDGE_BIASING_STROKE_COLOR: &str = "#c0004d";
+const EDGE_CYCLE_STROKE_COLOR: &str = "#ff7f00";
+
+/// Fill and stroke colours of a vertex.
+pub fn vertex_colors(vertex_type: VertexType) -> (&'static str, &'static str) {
+    match vertex_type {
+        VertexType::None => ("#aaaaaa", "#666666"),
+        VertexType::Exposure => ("#bed403", "#000000"),
+        VertexType::Outcome => ("#00a2e0", "#000000"),
+        VertexType::Selected => ("#aaaaaa", "#666666"),
+        VertexType::Adjusted => ("#ffffff", "#000000"),
+        VertexType::Unobserved => ("#00a2e0", "#000000"),
+    }
+}
+
+/// Mapping between model coordinates and a drawing area in pixels.
+#[derive(Copy, Clone, Debug)]
+pub struct Bounds {
+    min_x: f64,
+    max_x: f64,
+    min_y: f64,
+    max_y: f64,
+    /// Height of the drawing area.
+    pub height: f64,
+    /// Width of the drawing area.
+    pub width: f64,
+}
+
+impl Bounds {
+    /// Fit the graph bounding box, or its nodes, in a drawing area leaving room
+    /// for the vertices at the borders.
+    pub fn calculate_bounds(
+        graph: &CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>,
+        height: i32,
+        width: i32,
+    ) -> Self {
+        let mut min_x = f64::MAX;
+        let mut max_x = f64::MIN;
+        let mut min_y = f64::MAX;
+        let mut max_y = f64::MIN;
+        let height = if height > 0 { height as f64 } else { 0.0 };
+
+        let width = if width > 0 { width as f64 } else { 0.0 };
+
+        if let Some((min, max)) = graph.bounding_box() {
+            min_x = min.x();
+            max_x = max.x();
+            min_y = min.y();
+            max_y = max.y();
+        } else {
+            for idx in graph.node_indices() {
+                let pos = graph.node_weight(idx).unwrap().layout_pos.get();
+                min_x = min_x.min(pos.x());
+                max_x = max_x.max(pos.x());
+                min_y = min_y.min(pos.y());
+                max_y = max_y.max(pos.y());
+            }
+        }
+        if max_x == min_x {
+            max_x = min_x + 1.0
+        }
+        if max_y == min_y {
+            max_y = min_y + 1.0
+        }
+        let xpad = 50.0 / width * (max_x - min_x);
+        let ypad = 80.0 / height * (max_y - min_y);
+        min_x -= xpad;
+        max_x += xpad;
+        min_y -= ypad;
+        max_y += ypad;
+
+        Self {
+            min_x,
+            max_x,
+            min_y,
+            max_y,
+            height,
+            width,
+        }
+    }
+
+    /// Pixel position of a point of the model.
+    pub fn to_svg_coordinates(self, pos: &Point<f64>) -> Point<f64> {
+        let x = (pos.x() - self.min_x) / (self.max_x - self.min_x) * self.width;
+        let y = (pos.y() - self.min_y) / (self.max_y - self.min_y) * self.height;
+        Point::new(x, y)
+    }
+
+    /// Model position of a pixel.
+    pub fn to_graph_coordinates(self, pos: &Point<f64>) -> Point<f64> {
+        let x = pos.x() / self.width * (self.max_x - self.min_x) + self.min_x;
+        let y = pos.y() / self.height * (self.max_y - self.min_y) + self.min_y;
+        Point::new(x, y)
+    }
+}
+
+/// Point where the ray from the centre of a vertex ellipse towards `towards`
+/// leaves it, moved `gap` pixels further along the ray.
+pub fn ellipse_anchor(center: &Point<f64>, towards: &Point<f64>, gap: f64) -> Point<f64> {
+    let (rx, ry) = VERTEX_RADII;
+    let dx = towards.x() - center.x();
+    let dy = towards.y() - center.y();
+    let length = f64::sqrt(dx * dx + dy * dy);
+    if length < 0.01 {
+        return Point::new(center.x() + rx + gap, center.y());
+    }
+    let (ux, uy) = (dx / length, dy / length);
+    let radius = 1.0 / f64::sqrt((ux / rx).powi(2) + (uy / ry).powi(2)) + gap;
+    Point::new(center.x() + ux * radius, center.y() + uy * radius)
+}
+
+/// Rotation in degrees turning the positive x axis from `anchor` towards `from`,
+/// to draw the mark at the end of an edge coming from `from`.
+pub fn mark_angle(anchor: &Point<f64>, from: &Point<f64>) -> f64 {
+    f64::atan2(from.y() - anchor.y(), from.x() - anchor.x()).to_degrees()
+}
+
+/// Pixel geometry of an edge.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EdgeGeometry {
+    /// Where the edge leaves the source vertex.
+    pub source: Point<f64>,
+    /// Where the edge reaches the target vertex.
+    pub target: Point<f64>,
+    /// Control point of the quadratic curve, `None` for straight edges.
+    pub control: Option<Point<f64>>,
+}
+
+impl EdgeGeometry {
+    /// Edge between vertices centred at `source` and `target`, curved through
+    /// `control` if given, ending `MARK_GAP` pixels away from the vertices where
+    /// there are marks.
+    pub fn new(
+        source: &Point<f64>,
+        target: &Point<f64>,
+        control: Option<Point<f64>>,
+        marks: (Mark, Mark),
+    ) -> Self {
+        let gap = |mark: Mark| if mark == Mark::Tail { 0.0 } else { MARK_GAP };
+        Self {
+            source: ellipse_anchor(source, &control.unwrap_or(*target), gap(marks.0)),
+            target: ellipse_anchor(target, &control.unwrap_or(*source), gap(marks.1)),
+            control,
+        }
+    }
+
+    /// SVG path data of the edge line.
+    pub fn path(&self) -> String {
+        match self.control {
+            Some(p) => format!(
+                "M{:.2},{:.2}Q{:.2},{:.2},{:.2},{:.2}",
+                self.source.x(),
+                self.source.y(),
+                p.x(),
+                p.y(),
+                self.target.x(),
+                self.target.y()
+            ),
+            None => format!(
+                "M{:.2},{:.2}L{:.2},{:.2}",
+                self.source.x(),
+                self.source.y(),
+                self.target.x(),
+                self.target.y()
+            ),
+        }
+    }
+
+    /// SVG transform placing a mark at the target end, or the source end if not `front`.
+    pub fn mark_transform(&self, front: bool) -> String {
+        let (anchor, other) = if front {
+            (self.target, self.source)
+        } else {
+            (self.source, self.target)
+        };
+        let from = self.control.unwrap_or(other);
+        format!(
+            "translate({}, {}) rotate({})",
+            anchor.x(),
+            anchor.y(),
+            mark_angle(&anchor, &from)
+        )
+    }
+}
+
+/// Escape text for SVG content and attribute values.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>> {
+    /// Draw the graph as a standalone SVG document of the given size in pixels,
+    /// highlighting biasing edges and cycles like the interactive graph.
+    pub fn to_svg(&self, width: i32, height: i32) -> String {
+        let bounds = Bounds::calculate_bounds(self, height, width);
+        let biasing_edges = self.biasing_edges();
+        let cycle_edges = self.cycle_edges();
+        let center = |pos: Point<f64>| bounds.to_svg_coordinates(&pos);
+
+        let mut result = String::new();
+        let _ = writeln!(
+            result,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" style="font-family: Arial, sans-serif">"#,
+            bounds.width, bounds.height, bounds.width, bounds.height
+        );
+
+        for node_index in self.node_indices() {
+            let node = self.node_weight(node_index).unwrap();
+            let pos = center(node.layout_pos.get());
+            let (fill, stroke) = vertex_colors(node.vertex_type.get());
+            let _ = writeln!(
+                result,
+                r#"<g transform="translate({}, {})"><path d="{}" fill="{}" fill-opacity="0.7" stroke="{}" stroke-width="1.5"/><text text-anchor="middle" y="35">{}</text></g>"#,
+                pos.x(),
+                pos.y(),
+                VERTEX_PATH,
+                fill,
+                stroke,
+                escape(&name(&node.id.lock_ref()))
+            );
+        }
+
+        for edge_index in self.edge_indices() {
+            let edge = self.edge_weight(edge_index).unwrap();
+            let (source, target) = self.edge_endpoints(edge_index).unwrap();
+            let marks = edge.edge_type().marks();
+            let geometry = EdgeGeometry::new(
+                &center(self.node_weight(source).unwrap().layout_pos.get()),
+                &center(self.node_weight(target).unwrap().layout_pos.get()),
+                edge.layout_pos.get().map(center),
+                marks,
+            );
+            let stroke = edge_stroke_color(edge_index, &biasing_edges, &cycle_edges);
+            let _ = write!(
+                result,
+                r#"<g><path d="{}" fill="none" stroke="{}" stroke-width="1.5"/>"#,
+                geometry.path(),
+                stroke
+            );
+            for (front, mark) in [(true, marks.1), (false, marks.0)] {
+                let mark_path = match mark {
+                    Mark::Arrow => ARROW_PATH,
+                    Mark::Circle => CIRCLE_PATH,
+                    Mark::Tail => continue,
+                };
+                let _ = write!(
+                    result,
+                    r#"<path d="{}" fill="white" stroke="{}" stroke-width="1.5" transform="{}"/>"#,
+                    mark_path,
+                    stroke,
+                    geometry.mark_transform(front)
+                );
+            }
+            result.push_str("</g>\n");
+        }
+
+        result.push_str("</svg>\n");
+        result
+    }
+}
+
+/// Stroke colour of an edge, cycles taking precedence over biasing paths.
+pub fn edge_stroke_color(
+    edge: EdgeIndex,
+    biasing_edges: &[EdgeIndex],
+    cycle_edges: &[EdgeIndex],
+) -> &'static str {
+    if cycle_edges.contains(&edge) {
+        EDGE_CYCLE_STROKE_COLOR
+    } else if biasing_edges.contains(&edge) {
+        EDGE_BIASING_STROKE_COLOR
+    } else {
+        EDGE_STROKE_COLOR
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::dagitty::EdgeType;
+    use crate::graph::{GraphKind, StableDiGraph};
+
+    fn assert_close(p: Point<f64>, x: f64, y: f64) {
+        assert!(
+            (p.x() - x).abs() < 1e-9 && (p.y() - y).abs() < 1e-9,
+            "{} != {},{}",
+            p,
+            x,
+            y
+        );
+    }
+
+    #[test]
+    fn test_ellipse_anchor() {
+        let c = Point::new(100.0, 100.0);
+        assert_close(
+            ellipse_anchor(&c, &Point::new(200.0, 100.0), 0.0),
+            120.0,
+            100.0,
+        );
+        assert_close(
+            ellipse_anchor(&c, &Point::new(100.0, 0.0), 0.0),
+            100.0,
+            85.0,
+        );
+        assert_close(
+            ellipse_anchor(&c, &Point::new(0.0, 100.0), 5.0),
+            75.0,
+            100.0,
+        );
+        // On the ellipse in any direction.
+        let p = ellipse_anchor(&c, &Point::new(130.0, 140.0), 0.0);
+        let (rx, ry) = VERTEX_RADII;
+        let on = ((p.x() - 100.0) / rx).powi(2) + ((p.y() - 100.0) / ry).powi(2);
+        assert!((on - 1.0).abs() < 1e-9);
+        assert!((mark_angle(&Point::new(0.0, 0.0), &Point::new(0.0, -1.0)) + 90.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_edge_geometry() {
+        let source = Point::new(0.0, 0.0);
+        let target = Point::new(100.0, 0.0);
+        let straight = EdgeGeometry::new(&source, &target, None, (Mark::Tail, Mark::Arrow));
+        assert_close(straight.source, 20.0, 0.0);
+        assert_close(straight.target, 75.0, 0.0);
+        assert_eq!(straight.path(), "M20.00,0.00L75.00,0.00");
+        assert_eq!(
+            straight.mark_transform(true),
+            "translate(75, 0) rotate(180)"
+        );
+
+        let curved = EdgeGeometry::new(
+            &source,
+            &target,
+            Some(Point::new(50.0, 50.0)),
+            (Mark::Arrow, Mark::Tail),
+        );
+        assert!(curved.path().contains('Q'));
+        assert!(curved.source.y() > 0.0 && curved.target.y() > 0.0);
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let mut g = StableDiGraph::new();
+        let a = g.add_node(Arc::new(NodeInfo::new("A", 0.0, 0.0, VertexType::Exposure)));
+        let b = g.add_node(Arc::new(NodeInfo::new(
+            "\"B<1>\"",
+            1.0,
+            1.0,
+            VertexType::Outcome,
+        )));
+        g.add_edge(
+            a,
+            b,
+            Arc::new(EdgeInfo::new("", None, EdgeType::Bidirected)),
+        );
+        let graph: CausalGraph<_, _> = GraphKind::Dag(g).into();
+        let svg = graph.to_svg(400, 300);
+        assert!(
+            svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="400" height="300""#)
+        );
+        assert!(svg.contains(r##"fill="#bed403""##));
+        assert!(svg.contains(">B&lt;1&gt;<"));
+        assert_eq!(svg.matches(ARROW_PATH).count(), 2);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn test_to_svg_after_removals() {
+        let mut g = StableDiGraph::new();
+        let ids = ["A", "B", "C"];
+        let nodes: Vec<_> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| {
+                let node = NodeInfo::new(id, i as f64, 0.0, VertexType::None);
+                g.add_node(Arc::new(node))
+            })
+            .collect();
+        let edge = |t| Arc::new(EdgeInfo::new("", None, t));
+        g.add_edge(nodes[0], nodes[1], edge(EdgeType::Directed));
+        g.add_edge(nodes[1], nodes[2], edge(EdgeType::Directed));
+        let ac = g.add_edge(nodes[0], nodes[2], edge(EdgeType::Bidirected));
+        let mut graph: CausalGraph<_, _> = GraphKind::Dag(g).into();
+
+        // Indices held by the drawing elements still find their data.
+        graph.remove_node(nodes[1]);
+        assert_eq!("C", graph.node_weight(nodes[2]).unwrap().id.get_cloned());
+        assert_eq!(Some((nodes[0], nodes[2])), graph.edge_endpoints(ac));
+        assert_eq!(
+            EdgeType::Bidirected,
+            graph.edge_weight(ac).unwrap().edge_type()
+        );
+
+        let svg = graph.to_svg(400, 300);
+        assert!(!svg.contains(">B<"));
+        assert!(svg.contains(">C<"));
+        assert_eq!(svg.matches(ARROW_PATH).count(), 2);
+
+        graph.remove_edge(ac);
+        assert!(!graph.to_svg(400, 300).contains(ARROW_PATH));
+    }
+
+    #[test]
+    fn test_indices_after_mixed_removals() {
+        let mut g = StableDiGraph::new();
+        let ids = ["A", "B", "C", "D", "E", "F"];
+        let nodes: Vec<_> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| {
+                let node = NodeInfo::new(id, i as f64, 0.0, VertexType::None);
+                g.add_node(Arc::new(node))
+            })
+            .collect();
+        let mut expected_edges = Vec::new();
+        for (s, t, edge_type) in [
+            (0, 1, EdgeType::Directed),
+            (1, 2, EdgeType::Bidirected),
+            (2, 3, EdgeType::Directed),
+            (3, 4, EdgeType::Undirected),
+            (4, 5, EdgeType::Directed),
+            (1, 4, EdgeType::Bidirected),
+            (2, 4, EdgeType::Directed),
+            (3, 1, EdgeType::Directed),
+        ] {
+            let info = Arc::new(EdgeInfo::new("", None, edge_type));
+            let index = g.add_edge(nodes[s], nodes[t], info);
+            expected_edges.push((index, ids[s], ids[t], edge_type));
+        }
+        let mut graph: CausalGraph<_, _> = GraphKind::Dag(g).into();
+
+        // Edges first and last, nodes first and last, with a node and an edge
+        // in between.
+        let last_edge = expected_edges.len() - 1;
+        graph.remove_edge(expected_edges[last_edge].0);
+        graph.remove_node(nodes[0]);
+        graph.remove_edge(expected_edges[2].0);
+        graph.remove_node(nodes[5]);
+        graph.remove_node(nodes[3]);
+        graph.remove_edge(expected_edges[1].0);
+        let removed_edges = [0, 1, 2, 3, 4, last_edge];
+        let removed_nodes = [0, 3, 5];
+
+        let surviving_nodes: Vec<_> = (0..ids.len())
+            .filter(|i| !removed_nodes.contains(i))
+            .collect();
+        assert_eq!(
+            surviving_nodes
+                .iter()
+                .map(|i| nodes[*i])
+                .collect::<Vec<_>>(),
+            graph.node_indices()
+        );
+        for (i, node) in nodes.iter().enumerate() {
+            let id = graph.node_weight(*node).map(|n| n.id.get_cloned());
+            if removed_nodes.contains(&i) {
+                assert_eq!(None, id);
+            } else {
+                assert_eq!(Some(ids[i].to_string()), id);
+            }
+        }
+
+        let mut surviving_edges = Vec::new();
+        for (i, (index, source, target, edge_type)) in expected_edges.iter().enumerate() {
+            if removed_edges.contains(&i) {
+                assert_eq!(None, graph.edge_endpoints(*index));
+                assert!(graph.edge_weight(*index).is_none());
+                continue;
+            }
+            surviving_edges.push(*index);
+            let (s, t) = graph.edge_endpoints(*index).unwrap();
+            assert_eq!(*source, graph.node_weight(s).unwrap().id.get_cloned());
+            assert_eq!(*target, graph.node_weight(t).unwrap().id.get_cloned());
+            assert_eq!(*edge_type, graph.edge_weight(*index).unwrap().edge_type());
+        }
+        assert_eq!(surviving_edges, graph.edge_indices());
+
+        let svg = graph.to_svg(400, 300);
+        for (i, id) in ids.iter().enumerate() {
+            assert_eq!(
+                !removed_nodes.contains(&i),
+                svg.contains(&format!(">{}<", id))
+            );
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::fmt::Display;
 
 /// Store 2D Point information
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point<T: Copy> {
     x: T,
     y: T,
@@ -24,8 +25,8 @@ impl<T: Copy> Point<T> {
     }
 }
 
-impl<T: Copy + Display> ToString for Point<T> {
-    fn to_string(&self) -> String {
-        format!("{},{}", self.x, self.y)
+impl<T: Copy + Display> Display for Point<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
     }
 }
@@ -0,0 +1,248 @@
+#![allow(missing_docs)]
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::builder::CausalGraphBuilder;
+use crate::error::{Error, ErrorKind, Span};
+use pest::{iterators::Pair, Parser};
+use pest_derive::Parser;
+use why_data::graph::{
+    dagitty::{identifier, name, EdgeInfo, EdgeType, NodeInfo, VertexType},
+    CausalEdge, CausalGraph,
+};
+
+type Name = (String, Span);
+
+// Parser
+#[derive(Parser, Debug)]
+#[grammar = "bnlearn/bnlearn.pest"]
+pub struct ModelStringParser;
+
+impl ModelStringParser {
+    fn name(pair: Pair<'_, Rule>) -> Name {
+        (pair.as_str().to_string(), Span::from_pest(pair.as_span()))
+    }
+
+    /// Node and parents of a `[node|parent1:parent2]` term.
+    fn parse_local(pair: Pair<'_, Rule>) -> (Name, Vec<Name>) {
+        debug_assert!(Rule::local == pair.as_rule(), "Input must be a local rule");
+        let mut inner = pair.into_inner();
+        let node = Self::name(inner.next().unwrap());
+        let parents = match inner.next() {
+            Some(parents) => parents.into_inner().map(Self::name).collect(),
+            None => Vec::new(),
+        };
+        (node, parents)
+    }
+
+    /// Parse a bnlearn model string such as `[A][B|A][C|A:B]` to create a DAG.
+    ///
+    /// Parents may appear before their own term, but every node must have one, and
+    /// the edges must not form a cycle.
+    /// Nodes are placed in causal order.
+    pub fn parse_str(content: &str) -> Result<CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>, Error> {
+        let mut parser = ModelStringParser::parse(Rule::model_string, content)?;
+        let locals: Vec<(Name, Vec<Name>)> = parser
+            .next()
+            .unwrap()
+            .into_inner()
+            .filter(|pair| Rule::local == pair.as_rule())
+            .map(Self::parse_local)
+            .collect();
+
+        let mut ids = HashMap::new();
+        for ((node, span), _) in &locals {
+            if ids.insert(node.as_str(), identifier(node)).is_some() {
+                return Err(Error::new(
+                    ErrorKind::DuplicateNode,
+                    format!("node {} is declared more than once", node),
+                    *span,
+                ));
+            }
+        }
+
+        let mut builder = CausalGraphBuilder::<Arc<NodeInfo>, Arc<EdgeInfo>>::new().dag();
+        for ((node, _), _) in &locals {
+            let id = &ids[node.as_str()];
+            builder = builder.add_node(Arc::new(NodeInfo::new(id, 0.0, 0.0, VertexType::None)), id);
+        }
+        // Parent term of every edge, to point at the one closing a cycle.
+        let mut edge_spans = HashMap::new();
+        for ((node, _), parents) in &locals {
+            let node_id = &ids[node.as_str()];
+            for (parent, span) in parents {
+                let Some(parent_id) = ids.get(parent.as_str()) else {
+                    return Err(Error::new(
+                        ErrorKind::UndeclaredNode,
+                        format!("parent {} of {} is never declared", parent, node),
+                        *span,
+                    ));
+                };
+                if parent_id == node_id {
+                    return Err(Error::new(
+                        ErrorKind::Cycle,
+                        format!(
+                            "edge {} -> {} closes the cycle {} -> {}",
+                            node_id, node_id, node_id, node_id
+                        ),
+                        *span,
+                    ));
+                }
+                if edge_spans.insert((parent_id, node_id), *span).is_some() {
+                    return Err(Error::new(
+                        ErrorKind::DuplicateEdge,
+                        format!("parent {} of {} is given more than once", parent, node),
+                        *span,
+                    ));
+                }
+                let edge = EdgeInfo::new("", None, EdgeType::Directed);
+                builder = builder.add_edge(parent_id, node_id, Arc::new(edge));
+            }
+        }
+
+        let graph = builder.build();
+        if let Some(mut cycle) = graph.find_cycle() {
+            // Start at the source of the edge back to the first node.
+            cycle.rotate_right(1);
+            let id = |n| graph.node_weight(n).unwrap().id.get_cloned();
+            let (source, target) = (id(cycle[0]), id(cycle[1]));
+            return Err(Error::new(
+                ErrorKind::Cycle,
+                format!(
+                    "edge {} -> {} closes the cycle {}",
+                    source,
+                    target,
+                    graph.cycle_to_string(&cycle)
+                ),
+                edge_spans[&(&source, &target)],
+            ));
+        }
+        graph.layout_layers();
+        Ok(graph)
+    }
+}
+
+// Writer
+#[derive(Debug)]
+pub struct ModelStringWriter;
+
+impl ModelStringWriter {
+    /// Write a causal graph as a bnlearn model string, with nodes and parents in
+    /// graph order. `None` if it has edges other than directed ones or names a
+    /// model string can't hold.
+    pub fn write_str(graph: &CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>) -> Option<String> {
+        let mut parents = HashMap::new();
+        for edge_index in graph.edge_indices() {
+            if graph.edge_weight(edge_index)?.edge_type() != EdgeType::Directed {
+                return None;
+            }
+            let (source, target) = graph.edge_endpoints(edge_index)?;
+            parents.entry(target).or_insert_with(Vec::new).push(source);
+        }
+
+        let mut names = HashMap::new();
+        for node in graph.node_indices() {
+            let name = name(&graph.node_weight(node)?.id.lock_ref());
+            if name.is_empty()
+                || name
+                    .chars()
+                    .any(|c| "[]|:".contains(c) || c.is_whitespace())
+            {
+                return None;
+            }
+            names.insert(node, name);
+        }
+
+        let mut result = String::new();
+        for node in graph.node_indices() {
+            result.push('[');
+            result.push_str(&names[&node]);
+            if let Some(node_parents) = parents.get_mut(&node) {
+                node_parents.sort();
+                node_parents.dedup();
+                let node_parents: Vec<&str> =
+                    node_parents.iter().map(|p| names[p].as_str()).collect();
+                result.push('|');
+                result.push_str(&node_parents.join(":"));
+            }
+            result.push(']');
+        }
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dagitty::DagittyParser;
+    use why_data::graph::{GraphKind, NodeIndex};
+
+    #[test]
+    fn test_parse_model_string() {
+        let graph = ModelStringParser::parse_str("[A][C|A:B]\n[B|A]").unwrap();
+        assert!(matches!(graph.kind, GraphKind::Dag(_)));
+        let edges: Vec<(usize, usize)> = graph
+            .edge_indices()
+            .into_iter()
+            .map(|e| {
+                let (s, t) = graph.edge_endpoints(e).unwrap();
+                (s.index(), t.index())
+            })
+            .collect();
+        assert_eq!(edges, vec![(0, 1), (2, 1), (0, 2)]);
+        let y = |i: usize| {
+            graph
+                .node_weight(NodeIndex::new(i))
+                .unwrap()
+                .layout_pos
+                .get()
+                .y()
+        };
+        assert!(y(0) < y(2) && y(2) < y(1));
+        assert_eq!(
+            ModelStringWriter::write_str(&graph).unwrap(),
+            "[A][C|A:B][B|A]"
+        );
+    }
+
+    #[test]
+    fn test_model_string_errors() {
+        let error = ModelStringParser::parse_str("[A][B|A:]").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Syntax);
+
+        let error = ModelStringParser::parse_str("[A][B|A]\n[A]").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::DuplicateNode);
+        assert_eq!((error.span.line, error.span.column), (2, 2));
+
+        let error = ModelStringParser::parse_str("[A][B|A:C]").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UndeclaredNode);
+        assert_eq!(error.span.column, 9);
+
+        let error = ModelStringParser::parse_str("[A|A]").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Cycle);
+        assert_eq!(error.message, "edge A -> A closes the cycle A -> A");
+        assert_eq!(error.span.column, 4);
+
+        let error = ModelStringParser::parse_str("[A][B|A:A]").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::DuplicateEdge);
+        assert_eq!(error.span.column, 9);
+
+        let error = ModelStringParser::parse_str("[A|B][B|A]").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Cycle);
+        assert_eq!(error.message, "edge B -> A closes the cycle B -> A -> B");
+        assert_eq!(error.span.column, 4);
+    }
+
+    #[test]
+    fn test_write_model_string() {
+        let graph = DagittyParser::parse_str("dag { \"a b\" -> C }").unwrap();
+        assert_eq!(ModelStringWriter::write_str(&graph), None);
+        let graph = DagittyParser::parse_str("dag { A <-> C }").unwrap();
+        assert_eq!(ModelStringWriter::write_str(&graph), None);
+        let graph = DagittyParser::parse_str("dag { X1 -> Y X2 -> Y X1 -> X2 }").unwrap();
+        assert_eq!(
+            ModelStringWriter::write_str(&graph).unwrap(),
+            "[X1][Y|X1:X2][X2|X1]"
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use why_data::graph::{
+    dagitty::{EdgeType, Mark},
+    CausalEdge, CausalGraph, CausalGraphExt, GraphKind, NodeIndex, StableGraph, StableUnGraph,
+};
+
+use crate::error::{Span, Warning, WarningKind};
+
+/// Incremental construction of a causal graph from named nodes.
+pub(crate) struct CausalGraphBuilder<N, E> {
+    pub(crate) graph: Option<CausalGraph<N, E>>,
+    node_map: HashMap<String, NodeIndex>,
+    /// Nodes created by an edge and not declared yet, with the first edge using them.
+    pub(crate) undeclared: HashMap<String, Span>,
+    pub(crate) warnings: Vec<Warning>,
+}
+
+impl<N, E> CausalGraphBuilder<N, E> {
+    pub(crate) fn new() -> CausalGraphBuilder<N, E> {
+        Self {
+            graph: None,
+            node_map: HashMap::new(),
+            undeclared: HashMap::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub(crate) fn dag(mut self) -> CausalGraphBuilder<N, E> {
+        let g = StableGraph::<N, E>::new();
+        self.graph = Some(GraphKind::Dag(g).into());
+        self
+    }
+
+    pub(crate) fn graph(mut self) -> CausalGraphBuilder<N, E> {
+        let g = StableUnGraph::<N, E>::default();
+        self.graph = Some(GraphKind::Ungraph(g).into());
+        self
+    }
+
+    pub(crate) fn pdag(mut self) -> CausalGraphBuilder<N, E> {
+        self.graph = Some(GraphKind::Pdag(StableGraph::<N, E>::new()).into());
+        self
+    }
+
+    pub(crate) fn mag(mut self) -> CausalGraphBuilder<N, E> {
+        self.graph = Some(GraphKind::Mag(StableGraph::<N, E>::new()).into());
+        self
+    }
+
+    pub(crate) fn pag(mut self) -> CausalGraphBuilder<N, E> {
+        self.graph = Some(GraphKind::Pag(StableGraph::<N, E>::new()).into());
+        self
+    }
+
+    /// Directed graph of the simplest kind holding the given edges: a PAG with
+    /// circle marks, a PDAG with undirected edges, or a DAG.
+    pub(crate) fn mixed(self, edge_types: &[EdgeType]) -> CausalGraphBuilder<N, E> {
+        let marks: Vec<(Mark, Mark)> = edge_types.iter().map(|e| e.marks()).collect();
+        if marks
+            .iter()
+            .any(|(s, t)| *s == Mark::Circle || *t == Mark::Circle)
+        {
+            self.pag()
+        } else if marks.contains(&(Mark::Tail, Mark::Tail)) {
+            self.pdag()
+        } else {
+            self.dag()
+        }
+    }
+
+    pub(crate) fn add_node(mut self, n: N, id: &str) -> CausalGraphBuilder<N, E> {
+        if let Some(g) = self.graph.as_mut() {
+            self.node_map.insert(id.into(), g.add_node(n));
+        }
+        self
+    }
+
+    pub(crate) fn warn(&mut self, kind: WarningKind, message: String, span: Span) {
+        self.warnings.push(Warning::new(kind, message, span));
+    }
+
+    pub(crate) fn contains_node(&self, id: &str) -> bool {
+        self.node_map.contains_key(id)
+    }
+
+    pub(crate) fn node_mut(&mut self, id: &str) -> Option<&mut N> {
+        let node = *self.node_map.get(id)?;
+        self.graph.as_mut()?.node_weight_mut(node)
+    }
+
+    pub(crate) fn add_attribute(mut self, key: &str, value: &str) -> CausalGraphBuilder<N, E> {
+        if let Some(g) = self.graph.as_mut() {
+            g.attributes.insert(key.into(), value.into());
+        }
+        self
+    }
+
+    pub(crate) fn add_edge(
+        mut self,
+        left_node: &str,
+        right_node: &str,
+        edge: E,
+    ) -> CausalGraphBuilder<N, E> {
+        let left_id = self.node_map.get(left_node);
+        let right_id = self.node_map.get(right_node);
+
+        if let (Some(g), Some(left_id), Some(right_id)) = (self.graph.as_mut(), left_id, right_id) {
+            g.add_edge(*left_id, *right_id, edge);
+        }
+
+        self
+    }
+
+    pub(crate) fn build(self) -> CausalGraph<N, E> {
+        self.graph.unwrap()
+    }
+}
+
+impl<N, E: CausalEdge> CausalGraphBuilder<N, E> {
+    /// Cycle a directed edge from left to right would close in a dag.
+    pub(crate) fn closes_cycle(&self, left_node: &str, right_node: &str) -> Option<Vec<NodeIndex>> {
+        let left_id = self.node_map.get(left_node)?;
+        let right_id = self.node_map.get(right_node)?;
+        match &self.graph.as_ref()?.kind {
+            GraphKind::Dag(g) => g.closes_cycle(*left_id, *right_id),
+            _ => None,
+        }
+    }
+}
@@ -1,13 +1,15 @@
 #![allow(missing_docs)]
 
-use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+use std::{marker::PhantomData, sync::Arc};
 
-use pest::{error::Error, iterators::Pair, Parser};
+use crate::builder::CausalGraphBuilder;
+use crate::error::{Error, ErrorKind, Span, Warning, WarningKind};
+use pest::{iterators::Pair, Parser};
 use pest_derive::Parser;
 use why_data::{
     graph::{
         dagitty::{EdgeInfo, EdgeType, NodeInfo, VertexType},
-        CausalGraph, Graph, NodeIndex, UnGraph,
+        CausalGraph,
     },
     types::Point,
 };
@@ -18,13 +20,30 @@ use why_data::{
 pub struct DagittyParser;
 
 impl DagittyParser {
-    fn parse_attr_value_position(pos: &str) -> (f64, f64) {
+    fn parse_attr_value_position(pos: &str) -> Option<(f64, f64)> {
         let pos = pos.replace('"', "");
-        let (x, y) = pos[..].split_at(pos.find(',').unwrap_or(0));
-        (
-            x.parse::<f64>().unwrap_or(0.0),
-            y[1..].parse::<f64>().unwrap_or(0.0),
-        )
+        let (x, y) = pos.split_once(',')?;
+        Some((x.trim().parse::<f64>().ok()?, y.trim().parse::<f64>().ok()?))
+    }
+
+    fn position(
+        value: &str,
+        span: Span,
+        builder: &CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>,
+    ) -> Result<(f64, f64), Error> {
+        Self::parse_attr_value_position(value).ok_or_else(|| {
+            let mut error = Error::new(
+                ErrorKind::InvalidPosition,
+                format!("invalid position {}, expected \"x,y\"", value),
+                span,
+            );
+            error.warnings = builder.warnings.clone();
+            error
+        })
+    }
+
+    fn node_id(pair: Pair<'_, Rule>) -> &str {
+        pair.into_inner().next().unwrap().as_str()
     }
 
     fn parse_edge_rhs(
@@ -32,51 +51,61 @@ impl DagittyParser {
         mut builder: CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>,
         left_node_id: &str,
         pos: Option<(f64, f64)>,
-    ) -> Result<CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>, Error<Rule>> {
+        attributes: &[(String, String)],
+    ) -> Result<CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>, Error> {
         debug_assert!(
             Rule::edge_rhs == pair.as_rule(),
             "Input must be an edge_rhs rule"
         );
         let mut inners = pair.into_inner();
-        let edgeop = inners.next().unwrap().as_str();
-        let node_id = inners
-            .next()
-            .map(|p| {
-                let mut inners = p.into_inner();
-                inners.next().unwrap().as_str()
-            })
-            .unwrap();
-
-        match edgeop {
-            "@->" | "->" => {
-                let edge = Arc::new(EdgeInfo::new(
-                    "",
-                    pos.map(|p| Point::new(p.0, p.1)),
-                    EdgeType::Directed,
-                ));
-                builder = builder.add_edge(left_node_id, node_id, edge);
-            }
-            "<-@" | "<-" => {
-                let edge = Arc::new(EdgeInfo::new(
-                    "",
-                    pos.map(|p| Point::new(p.0, p.1)),
-                    EdgeType::Directed,
-                ));
-                builder = builder.add_edge(node_id, left_node_id, edge);
-            }
-            "--@" | "--" | "<->" | "@-@" | "@--" => {
-                let edge = Arc::new(EdgeInfo::new(
-                    "",
-                    pos.map(|p| Point::new(p.0, p.1)),
-                    EdgeType::Undirected,
-                ));
-                builder = builder.add_edge(left_node_id, node_id, edge);
-            }
+        let edgeop = inners.next().unwrap();
+        let node = inners.next().unwrap();
+        let span = Span::from_pest(edgeop.as_span().start_pos().span(&node.as_span().end_pos()));
+        let edgeop = edgeop.as_str();
+        let node_id = Self::node_id(node);
+
+        // Edges are stored left to right unless the operator points to the left.
+        let (edge_type, reversed) = match edgeop {
+            "->" => (EdgeType::Directed, false),
+            "<-" => (EdgeType::Directed, true),
+            "<->" => (EdgeType::Bidirected, false),
+            "--" => (EdgeType::Undirected, false),
+            "@->" => (EdgeType::PartiallyDirected, false),
+            "<-@" => (EdgeType::PartiallyDirected, true),
+            "@-@" => (EdgeType::Nondirected, false),
+            "--@" => (EdgeType::PartiallyUndirected, false),
+            "@--" => (EdgeType::PartiallyUndirected, true),
             _ => unreachable!(),
+        };
+        let mut edge = EdgeInfo::new("", pos.map(|p| Point::new(p.0, p.1)), edge_type);
+        edge.attributes = attributes.to_vec();
+        let edge = Arc::new(edge);
+        let (source, target) = if reversed {
+            (node_id, left_node_id)
+        } else {
+            (left_node_id, node_id)
+        };
+        for id in [source, target] {
+            if !builder.contains_node(id) {
+                let node_info = NodeInfo::new(id, 0.0, 0.0, VertexType::None);
+                builder = builder.add_node(Arc::new(node_info), id);
+                builder.undeclared.insert(id.to_string(), span);
+            }
+        }
+        if let (EdgeType::Directed, Some(cycle)) = (edge_type, builder.closes_cycle(source, target))
+        {
+            let cycle = builder.graph.as_ref().unwrap().cycle_to_string(&cycle);
+            builder.warn(
+                WarningKind::Cycle,
+                format!("edge {} -> {} closes the cycle {}", source, target, cycle),
+                span,
+            );
         }
+        builder = builder.add_edge(source, target, edge);
 
         if inners.peek().is_some() {
-            builder = Self::parse_edge_rhs(inners.next().unwrap(), builder, node_id, pos)?;
+            builder =
+                Self::parse_edge_rhs(inners.next().unwrap(), builder, node_id, pos, attributes)?;
         }
 
         Ok(builder)
@@ -85,85 +114,135 @@ impl DagittyParser {
     fn parse_edge(
         pair: Pair<'_, Rule>,
         mut builder: CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>,
-    ) -> Result<CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>, Error<Rule>> {
+    ) -> Result<CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>, Error> {
         let mut pos = None;
+        let mut attributes = Vec::new();
         let mut inners = pair.into_inner();
-        let node_id = inners
-            .next()
-            .map(|p| {
-                let mut inners = p.into_inner();
-                inners.next().unwrap().as_str()
-            })
-            .unwrap();
+        let node_id = Self::node_id(inners.next().unwrap());
         let edge_rhs = inners.next().unwrap();
-        let attrs = inners.next().map(|p| AttrList::parse(p).unwrap());
-        if let Some(attrs) = attrs {
-            for alist in attrs.elems {
+        if let Some(p) = inners.next() {
+            let span = Span::from_pest(p.as_span());
+            for alist in AttrList::parse(p).elems {
                 for attr in alist.elems {
-                    if let ("pos", position) = attr {
-                        pos = Some(Self::parse_attr_value_position(position));
+                    match attr {
+                        ("pos", position) => {
+                            pos = Some(Self::position(position, span, &builder)?);
+                        }
+                        (key, value) => {
+                            builder.warn(
+                                WarningKind::UnknownAttribute,
+                                format!("unknown edge attribute {}", key),
+                                span,
+                            );
+                            attributes.push((key.to_string(), value.to_string()));
+                        }
                     }
                 }
             }
         }
 
-        builder = Self::parse_edge_rhs(edge_rhs, builder, node_id, pos)?;
+        builder = Self::parse_edge_rhs(edge_rhs, builder, node_id, pos, &attributes)?;
         Ok(builder)
     }
 
     fn parse_node(
         pair: Pair<'_, Rule>,
         mut builder: CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>,
-    ) -> Result<CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>, Error<Rule>> {
-        let mut vertex_type = VertexType::None;
-        let mut pos = (0.0, 0.0);
+    ) -> Result<CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>, Error> {
+        let mut vertex_type = None;
+        let mut pos = None;
+        let mut attributes = Vec::new();
+        let span = Span::from_pest(pair.as_span());
         let mut inners = pair.into_inner();
-        let node_id = inners
-            .next()
-            .map(|p| {
-                let mut inners = p.into_inner();
-                inners.next().unwrap().as_str()
-            })
-            .unwrap();
-        let attrs = inners.next().map(|p| AttrList::parse(p).unwrap());
-        if let Some(attrs) = attrs {
-            for alist in attrs.elems {
+        let node_id = Self::node_id(inners.next().unwrap());
+        let implicit = builder.undeclared.remove(node_id).is_some();
+        if builder.contains_node(node_id) && !implicit {
+            builder.warn(
+                WarningKind::DuplicateNode,
+                format!(
+                    "node {} is declared more than once, attributes are merged",
+                    node_id
+                ),
+                span,
+            );
+        }
+        if let Some(p) = inners.next() {
+            let span = Span::from_pest(p.as_span());
+            for alist in AttrList::parse(p).elems {
                 for attr in alist.elems {
                     match attr {
-                        ("adjusted", _) | ("a", _) => vertex_type = VertexType::Adjusted,
+                        ("adjusted", _) | ("a", _) => vertex_type = Some(VertexType::Adjusted),
                         ("source", _) | ("exposure", _) | ("e", _) => {
-                            vertex_type = VertexType::Exposure
+                            vertex_type = Some(VertexType::Exposure)
                         }
                         ("outcome", _) | ("target", _) | ("o", _) => {
-                            vertex_type = VertexType::Outcome
+                            vertex_type = Some(VertexType::Outcome)
                         }
-                        ("selected", _) | ("s", _) => vertex_type = VertexType::Selected,
+                        ("selected", _) | ("s", _) => vertex_type = Some(VertexType::Selected),
                         ("latent", _) | ("l", _) | ("unobserved", _) | ("u", _) => {
-                            vertex_type = VertexType::Unobserved
+                            vertex_type = Some(VertexType::Unobserved)
                         }
                         ("pos", position) => {
-                            pos = Self::parse_attr_value_position(position);
+                            pos = Some(Self::position(position, span, &builder)?);
+                        }
+                        (key, value) => {
+                            builder.warn(
+                                WarningKind::UnknownAttribute,
+                                format!("unknown attribute {} of node {}", key, node_id),
+                                span,
+                            );
+                            attributes.push((key.to_string(), value.to_string()));
                         }
-                        (_, _) => (),
                     }
                 }
             }
         }
-        let node_info = Arc::new(NodeInfo::new(node_id, pos.0, pos.1, vertex_type));
-        builder = builder.add_node(node_info, node_id);
+        // Nodes created by an edge or declared before take the new attributes.
+        if let Some(node_info) = builder.node_mut(node_id) {
+            let node_info = Arc::make_mut(node_info);
+            if let Some(vertex_type) = vertex_type {
+                node_info.vertex_type.set(vertex_type);
+            }
+            if let Some((x, y)) = pos {
+                node_info.layout_pos.set(Point::new(x, y));
+            }
+            for (key, value) in attributes {
+                match node_info.attributes.iter_mut().find(|(k, _)| *k == key) {
+                    Some(attribute) => attribute.1 = value,
+                    None => node_info.attributes.push((key, value)),
+                }
+            }
+        } else {
+            let (x, y) = pos.unwrap_or((0.0, 0.0));
+            let mut node_info =
+                NodeInfo::new(node_id, x, y, vertex_type.unwrap_or(VertexType::None));
+            node_info.attributes = attributes;
+            builder = builder.add_node(Arc::new(node_info), node_id);
+        }
 
         Ok(builder)
     }
 
+    fn parse_global_option(
+        pair: Pair<'_, Rule>,
+        builder: CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>,
+    ) -> Result<CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>, Error> {
+        let mut inners = pair.into_inner().next().unwrap().into_inner();
+        let key = inners.next().unwrap().as_str();
+        let value = inners.next().unwrap().as_str();
+
+        Ok(builder.add_attribute(key, value))
+    }
+
     fn parse_stmt(
         pair: Pair<'_, Rule>,
         mut builder: CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>,
-    ) -> Result<CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>, Error<Rule>> {
+    ) -> Result<CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>, Error> {
         let inner = pair.into_inner().next().unwrap();
         builder = match inner.as_rule() {
             Rule::node_stmt => Self::parse_node(inner, builder)?,
             Rule::edge_stmt => Self::parse_edge(inner, builder)?,
-            Rule::global_option => todo!(),
+            Rule::global_option => Self::parse_global_option(inner, builder)?,
             _ => unreachable!(),
         };
 
@@ -172,7 +251,7 @@ impl DagittyParser {
     fn parse_stmts(
         pair: Pair<'_, Rule>,
         mut builder: CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>,
-    ) -> Result<CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>, Error<Rule>> {
+    ) -> Result<CausalGraphBuilder<Arc<NodeInfo>, Arc<EdgeInfo>>, Error> {
         debug_assert!(
             Rule::stmt_list == pair.as_rule(),
             "Input must be a stmt_list rule"
@@ -190,29 +269,84 @@ impl DagittyParser {
         Ok(builder)
     }
 
+    /// Whether `id` is a single dagitty identifier: a name, a number, or a quoted
+    /// or html string.
+    pub fn is_identifier(id: &str) -> bool {
+        DagittyParser::parse(Rule::IDENTIFIER, id)
+            .ok()
+            .and_then(|mut pairs| pairs.next())
+            .is_some_and(|pair| pair.as_str().len() == id.len())
+    }
+
     /// Parse dagitty format to create a casual graph.
-    pub fn parse_str(
+    pub fn parse_str(content: &str) -> Result<CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>, Error> {
+        Self::parse_str_with_warnings(content).map(|(graph, _)| graph)
+    }
+
+    /// Parse dagitty format to create a casual graph, along with the warnings about
+    /// parts of the model that are likely mistakes.
+    #[allow(clippy::type_complexity)]
+    pub fn parse_str_with_warnings(
         content: &str,
-    ) -> Result<CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>, Error<Rule>> {
+    ) -> Result<(CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>, Vec<Warning>), Error> {
         let mut builder = CausalGraphBuilder::<Arc<NodeInfo>, Arc<EdgeInfo>>::new();
         let mut parser = DagittyParser::parse(Rule::dagitty_graph, content)?;
         let mut dagitty_g = parser.next().unwrap().into_inner();
-        let mut _strict = false;
+        let mut strict = false;
         let mut pair = dagitty_g.next().unwrap();
         if let Rule::STRICT = pair.as_rule() {
-            _strict = true;
+            strict = true;
             pair = dagitty_g.next().unwrap();
         }
 
-        builder = match pair.as_str() {
+        builder = match pair.as_str().to_lowercase().as_str() {
             "digraph" | "dag" => builder.dag(),
-            "graph" | "mag" | "pdag" | "pag" => builder.graph(),
+            "graph" => builder.graph(),
+            "pdag" => builder.pdag(),
+            "mag" => builder.mag(),
+            "pag" => builder.pag(),
             &_ => unreachable!("Unknown graph string"),
         };
 
-        builder = Self::parse_stmts(dagitty_g.next().unwrap(), builder)?;
+        let mut name = None;
+        let mut pair = dagitty_g.next().unwrap();
+        if let Rule::IDENTIFIER = pair.as_rule() {
+            name = Some(pair.as_str().to_string());
+            pair = dagitty_g.next().unwrap();
+        }
+
+        builder = Self::parse_stmts(pair, builder)?;
+
+        let mut undeclared: Vec<_> = builder.undeclared.drain().collect();
+        // Both ends of an edge share its span.
+        undeclared.sort_by(|(a, a_span), (b, b_span)| (a_span.start, a).cmp(&(b_span.start, b)));
+        for (id, span) in undeclared {
+            builder.warn(
+                WarningKind::UndeclaredNode,
+                format!("edge to undeclared node {}", id),
+                span,
+            );
+        }
+        builder.warnings.sort_by_key(|w| w.span.start);
+
+        let warnings = builder.warnings.clone();
+        let mut graph = builder.build();
+        graph.name = name;
+        graph.strict = strict;
+        Ok((graph, warnings))
+    }
+}
+
+// Writer
+#[derive(Debug)]
+pub struct DagittyWriter;
 
-        Ok(builder.build())
+impl DagittyWriter {
+    /// Write a causal graph in dagitty format.
+    ///
+    /// The output parses back with [`DagittyParser::parse_str`] to an equal graph.
+    pub fn write_str(graph: &CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>) -> String {
+        graph.to_string()
     }
 }
 
@@ -223,26 +357,22 @@ struct AttrList<'a, A = (&'a str, &'a str)> {
 }
 
 impl<'a> AttrList<'a> {
-    fn parse(p: Pair<'a, Rule>) -> Result<Self, ()> {
+    fn parse(p: Pair<'a, Rule>) -> Self {
         debug_assert!(
             Rule::attr_list == p.as_rule(),
             "Input must be an attr_list rule"
         );
         let mut v: Vec<AList<'a>> = Vec::new();
         let mut inners = p.into_inner();
-        let alist = AList::parse(inners.next().unwrap()).unwrap();
+        let alist = AList::parse(inners.next().unwrap());
         let mut tail = inners
             .next()
-            .map(|p| {
-                AttrList::parse(p)
-                    .map(|alist| alist.elems)
-                    .unwrap_or_default()
-            })
+            .map(|p| AttrList::parse(p).elems)
             .unwrap_or_default();
         v.push(alist);
         v.append(&mut tail);
 
-        Ok(AttrList { elems: v })
+        AttrList { elems: v }
     }
 }
 
@@ -254,7 +384,7 @@ struct AList<'a, A = (&'a str, &'a str)> {
 }
 
 impl<'a> AList<'a> {
-    fn parse(p: Pair<'a, Rule>) -> Result<Self, ()> {
+    fn parse(p: Pair<'a, Rule>) -> Self {
         debug_assert!(Rule::a_list == p.as_rule(), "Input must be an a_list rule");
         let mut v = Vec::new();
         let mut inners = p.into_inner();
@@ -270,71 +400,23 @@ impl<'a> AList<'a> {
         };
         let mut tail = inners
             .next()
-            .map(|p| AList::parse(p).map(|alist| alist.elems).unwrap_or_default())
+            .map(|p| AList::parse(p).elems)
             .unwrap_or_default();
         v.push((id1, id2));
         v.append(&mut tail);
 
-        Ok(AList {
+        AList {
             elems: v,
             _p: PhantomData,
-        })
-    }
-}
-
-struct CausalGraphBuilder<N, E> {
-    graph: Option<CausalGraph<N, E>>,
-    node_map: HashMap<String, NodeIndex>,
-}
-
-impl<N, E> CausalGraphBuilder<N, E> {
-    fn new() -> CausalGraphBuilder<N, E> {
-        Self {
-            graph: None,
-            node_map: HashMap::new(),
-        }
-    }
-
-    fn dag(mut self) -> CausalGraphBuilder<N, E> {
-        let g = Graph::<N, E>::new();
-        self.graph = Some(CausalGraph::Dag(g));
-        self
-    }
-
-    fn graph(mut self) -> CausalGraphBuilder<N, E> {
-        let g = UnGraph::<N, E>::new_undirected();
-        self.graph = Some(CausalGraph::Ungraph(g));
-        self
-    }
-
-    fn add_node(mut self, n: N, id: &str) -> CausalGraphBuilder<N, E> {
-        if self.graph.is_some() {
-            let g = self.graph.as_mut().unwrap();
-            self.node_map.insert(id.into(), g.add_node(n));
         }
-        self
-    }
-
-    fn add_edge(mut self, left_node: &str, right_node: &str, edge: E) -> CausalGraphBuilder<N, E> {
-        let left_id = self.node_map.get(left_node);
-        let right_id = self.node_map.get(right_node);
-
-        if self.graph.is_some() && left_id.is_some() && right_id.is_some() {
-            let g = self.graph.as_mut().unwrap();
-            g.add_edge(*left_id.unwrap(), *right_id.unwrap(), edge);
-        }
-
-        self
-    }
-
-    fn build(self) -> CausalGraph<N, E> {
-        self.graph.unwrap()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    use why_data::graph::{EdgeIndex, GraphKind, NodeIndex};
 
     const BASE_DAG_STR: &str = r#"dag {
 A [selected,pos="-2.200,-1.520"]
@@ -352,13 +434,381 @@ E -> D
     #[test]
     fn test_parsing_default_graph() {
         let graph = DagittyParser::parse_str(BASE_DAG_STR).unwrap();
-        if let CausalGraph::Dag(g) = graph {
+        if let GraphKind::Dag(g) = graph.kind {
             assert_eq!(5, g.node_count());
         } else {
             panic!("It should returned a valid Dag");
         }
     }
 
+    #[test]
+    fn test_adjustment_sets_default_graph() {
+        let graph = DagittyParser::parse_str(BASE_DAG_STR).unwrap();
+        let sets = graph.adjustment_sets().unwrap();
+        assert_eq!(
+            Some(vec![NodeIndex::new(0), NodeIndex::new(1)]),
+            sets.canonical
+        );
+        assert_eq!(vec![Vec::<NodeIndex>::new()], sets.minimal);
+    }
+
+    #[test]
+    fn test_implications_default_graph() {
+        let graph = DagittyParser::parse_str(BASE_DAG_STR).unwrap();
+        let implications = graph.basis_implications().unwrap();
+        assert_eq!("A _||_ B", graph.independence_to_string(&implications[0]));
+        assert_eq!(
+            "D _||_ A, Z | B, E",
+            graph.independence_to_string(&implications[2])
+        );
+    }
+
+    #[test]
+    fn test_paths_default_graph() {
+        let graph = DagittyParser::parse_str(BASE_DAG_STR).unwrap();
+        let paths: Vec<_> = graph.paths().unwrap().collect();
+        assert_eq!(2, paths.len());
+        assert!(paths.iter().all(|p| p.causal || !p.open));
+        assert!(graph.biasing_edges().is_empty());
+
+        // Without selecting A the back-door path through the collider Z opens.
+        let a = graph.nodes_of_type(VertexType::Selected)[0];
+        match &graph.kind {
+            GraphKind::Dag(g) => g[a].vertex_type.set(VertexType::None),
+            _ => unreachable!(),
+        }
+        assert_eq!(4, graph.biasing_edges().len());
+    }
+
+    #[test]
+    fn test_bidirected_edges() {
+        let graph = DagittyParser::parse_str(
+            r#"dag {
+X [exposure]
+Y [outcome]
+M
+X -> Y
+X <-> M
+M -> Y
+}"#,
+        )
+        .unwrap();
+        match &graph.kind {
+            GraphKind::Dag(g) => {
+                assert_eq!(3, g.edge_count());
+                let bidirected: Vec<_> = g
+                    .edge_indices()
+                    .filter(|e| *g[*e].edge_type.lock_ref() == EdgeType::Bidirected)
+                    .collect();
+                assert_eq!(1, bidirected.len());
+            }
+            _ => unreachable!(),
+        }
+        assert!(graph.to_string().contains("X <-> M"));
+        assert!(!graph.check_adjustment().unwrap().valid);
+
+        let graph = DagittyParser::parse_str(&graph.to_string()).unwrap();
+        assert!(graph.to_string().contains("X <-> M"));
+    }
+
+    #[test]
+    fn test_pag_edge_marks() {
+        let graph = DagittyParser::parse_str(
+            r#"pag {
+A
+B
+C
+D
+A @-> B
+C <-@ B
+C @-@ D
+A --@ D
+D @-- B
+B <-> D
+}"#,
+        )
+        .unwrap();
+        assert!(matches!(graph.kind, GraphKind::Pag(_)));
+
+        let edges: Vec<(String, EdgeType, String)> = graph
+            .edge_indices()
+            .into_iter()
+            .map(|e| {
+                let (s, t) = graph.edge_endpoints(e).unwrap();
+                (
+                    graph.node_weight(s).unwrap().id.get_cloned(),
+                    *graph.edge_weight(e).unwrap().edge_type.lock_ref(),
+                    graph.node_weight(t).unwrap().id.get_cloned(),
+                )
+            })
+            .collect();
+        let edge = |s: &str, t: EdgeType, d: &str| (s.to_string(), t, d.to_string());
+        assert_eq!(
+            vec![
+                edge("A", EdgeType::PartiallyDirected, "B"),
+                edge("B", EdgeType::PartiallyDirected, "C"),
+                edge("C", EdgeType::Nondirected, "D"),
+                edge("A", EdgeType::PartiallyUndirected, "D"),
+                edge("B", EdgeType::PartiallyUndirected, "D"),
+                edge("B", EdgeType::Bidirected, "D"),
+            ],
+            edges
+        );
+
+        let text = graph.to_string();
+        assert!(text.starts_with("pag {"));
+        assert!(text.contains("B @-> C"));
+        assert!(text.contains("B --@ D"));
+        let graph = DagittyParser::parse_str(&text).unwrap();
+        assert_eq!(text, graph.to_string());
+    }
+
+    #[test]
+    fn test_mixed_graph_types() {
+        let pdag = DagittyParser::parse_str("pdag { A B C A -> B B -- C }").unwrap();
+        assert!(matches!(pdag.kind, GraphKind::Pdag(_)));
+        assert!(pdag.to_string().contains("B -- C"));
+        let mag = DagittyParser::parse_str("mag { A B C A -> B B <-> C }").unwrap();
+        assert!(matches!(mag.kind, GraphKind::Mag(_)));
+        assert!(mag.to_string().contains("A -> B"));
+        assert!(mag.adjustment_sets().is_none());
+    }
+
+    #[test]
+    fn test_write_header_and_attributes() {
+        let text = r#"strict pdag model {
+A [exposure,pos="1,2",label="first",hidden]
+B [pos="0,0"]
+A -> B [pos="0.5,1",beta=0.3]
+B --@ A [style]
+}"#;
+        let graph = DagittyParser::parse_str(text).unwrap();
+        assert!(graph.strict);
+        assert_eq!(Some("model".to_string()), graph.name);
+        assert_eq!(text, DagittyWriter::write_str(&graph));
+    }
+
+    #[test]
+    fn test_global_options() {
+        let text = r#"dag {
+bb="-3,-2.5,4,3.5"
+label=model
+A [pos="0,0"]
+B [pos="1,1"]
+A -> B
+}"#;
+        let graph = DagittyParser::parse_str(text).unwrap();
+        assert_eq!(Some("model"), graph.attributes.get("label").map(|l| &l[..]));
+        assert_eq!(
+            Some((Point::new(-3.0, -2.5), Point::new(4.0, 3.5))),
+            graph.bounding_box()
+        );
+        assert_eq!(text, DagittyWriter::write_str(&graph));
+    }
+
+    #[test]
+    fn test_warnings() {
+        let (graph, warnings) = DagittyParser::parse_str_with_warnings(
+            r#"dag {
+A [pos="0,0",colour=red]
+B
+A
+A -> B
+B -> C
+B -> A
+}"#,
+        )
+        .unwrap();
+        let kinds: Vec<_> = warnings.iter().map(|w| (w.kind, w.span.line)).collect();
+        assert_eq!(
+            vec![
+                (WarningKind::UnknownAttribute, 2),
+                (WarningKind::DuplicateNode, 4),
+                (WarningKind::UndeclaredNode, 6),
+                (WarningKind::Cycle, 7),
+            ],
+            kinds
+        );
+        assert_eq!(
+            "7:3: warning: edge B -> A closes the cycle B -> A -> B",
+            warnings[3].to_string()
+        );
+        assert_eq!(3, graph.edge_indices().len());
+
+        for _ in 0..10 {
+            let (_, warnings) =
+                DagittyParser::parse_str_with_warnings("dag { Z <- Y -> X }").unwrap();
+            let messages: Vec<_> = warnings.iter().map(|w| w.to_string()).collect();
+            assert_eq!(
+                vec![
+                    "1:9: warning: edge to undeclared node Y",
+                    "1:9: warning: edge to undeclared node Z",
+                    "1:14: warning: edge to undeclared node X",
+                ],
+                messages
+            );
+        }
+        let cycle = graph.find_cycle().unwrap();
+        assert_eq!("A -> B -> A", graph.cycle_to_string(&cycle));
+        assert_eq!(
+            vec![EdgeIndex::new(0), EdgeIndex::new(2)],
+            graph.cycle_edges()
+        );
+    }
+
+    #[test]
+    fn test_implicit_nodes() {
+        let (graph, warnings) = DagittyParser::parse_str_with_warnings(
+            r#"dag {
+A -> B
+B [exposure]
+A [pos="1,2",label=a]
+A [outcome,label=b,size=2]
+}"#,
+        )
+        .unwrap();
+        assert_eq!(2, graph.node_indices().len());
+        assert_eq!(1, graph.edge_indices().len());
+        let a = graph.node_weight(NodeIndex::new(0)).unwrap();
+        let b = graph.node_weight(NodeIndex::new(1)).unwrap();
+        assert_eq!("A", a.id.get_cloned());
+        assert_eq!(VertexType::Outcome, a.vertex_type.get());
+        assert_eq!(Point::new(1.0, 2.0), a.layout_pos.get());
+        assert_eq!(
+            vec![
+                ("label".to_string(), "b".to_string()),
+                ("size".to_string(), "2".to_string())
+            ],
+            a.attributes
+        );
+        assert_eq!(VertexType::Exposure, b.vertex_type.get());
+        assert!(!warnings
+            .iter()
+            .any(|w| w.kind == WarningKind::UndeclaredNode));
+        assert_eq!(
+            1,
+            warnings
+                .iter()
+                .filter(|w| w.kind == WarningKind::DuplicateNode)
+                .count()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = DagittyParser::parse_str("dag {\nA [pos=\"1\"]\n}").unwrap_err();
+        assert_eq!(ErrorKind::InvalidPosition, error.kind);
+        assert_eq!((2, 3), (error.span.line, error.span.column));
+
+        let error = DagittyParser::parse_str("dag {\nA -> \n}").unwrap_err();
+        assert_eq!(ErrorKind::Syntax, error.kind);
+        assert_eq!(3, error.span.line);
+    }
+
+    fn arb_graph() -> impl Strategy<Value = CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>> {
+        let vertex_type = prop_oneof![
+            Just(VertexType::None),
+            Just(VertexType::Adjusted),
+            Just(VertexType::Exposure),
+            Just(VertexType::Outcome),
+            Just(VertexType::Selected),
+            Just(VertexType::Unobserved),
+        ];
+        let edge_type = prop_oneof![
+            Just(EdgeType::Directed),
+            Just(EdgeType::Bidirected),
+            Just(EdgeType::Undirected),
+            Just(EdgeType::PartiallyDirected),
+            Just(EdgeType::Nondirected),
+            Just(EdgeType::PartiallyUndirected),
+        ];
+        // Keys the parser interprets are never kept as attributes.
+        let attribute = (
+            "[b-df-kmnp-rt-z][a-z0-9_]{1,5}".prop_filter("known attribute", |k| {
+                !matches!(
+                    k.as_str(),
+                    "pos" | "source" | "target" | "selected" | "latent"
+                )
+            }),
+            prop_oneof![Just(String::new()), "[0-9]{1,3}", "\"[a-z ]{1,5}\""],
+        );
+        let position = (-1000.0..1000.0f64, -1000.0..1000.0f64);
+        let nodes = prop::collection::btree_map(
+            "[A-Za-z][A-Za-z0-9_]{0,6}",
+            (
+                vertex_type,
+                position.clone(),
+                prop::collection::vec(attribute.clone(), 0..3),
+            ),
+            1..8,
+        );
+        let edges = prop::collection::vec(
+            (
+                any::<prop::sample::Index>(),
+                any::<prop::sample::Index>(),
+                edge_type,
+                prop::option::of(position),
+                prop::collection::vec(attribute, 0..2),
+            ),
+            0..12,
+        );
+        let name = prop::option::of("[a-z][a-z0-9]{0,5}");
+        let options = prop::collection::btree_map(
+            "[a-z][a-z0-9_]{0,5}",
+            prop_oneof!["[0-9]{1,3}", "\"[a-z0-9,. -]{1,8}\""],
+            0..3,
+        );
+
+        (0..5usize, name, any::<bool>(), options, nodes, edges).prop_map(
+            |(kind, name, strict, options, nodes, edges)| {
+                let builder = CausalGraphBuilder::<Arc<NodeInfo>, Arc<EdgeInfo>>::new();
+                let mut builder = match kind {
+                    0 => builder.dag(),
+                    1 => builder.graph(),
+                    2 => builder.pdag(),
+                    3 => builder.mag(),
+                    _ => builder.pag(),
+                };
+                let ids: Vec<String> = nodes.keys().cloned().collect();
+                for (id, (vertex_type, (x, y), attributes)) in nodes {
+                    let mut node = NodeInfo::new(&id, x, y, vertex_type);
+                    node.attributes = attributes;
+                    builder = builder.add_node(Arc::new(node), &id);
+                }
+                for (source, target, edge_type, pos, attributes) in edges {
+                    let (source, target) = (source.get(&ids), target.get(&ids));
+                    if source == target {
+                        continue;
+                    }
+                    let edge_type = if kind == 1 {
+                        EdgeType::Undirected
+                    } else {
+                        edge_type
+                    };
+                    let mut edge = EdgeInfo::new("", pos.map(|(x, y)| Point::new(x, y)), edge_type);
+                    edge.attributes = attributes;
+                    builder = builder.add_edge(source, target, Arc::new(edge));
+                }
+                for (key, value) in options {
+                    builder = builder.add_attribute(&key, &value);
+                }
+                let mut graph = builder.build();
+                graph.name = name;
+                graph.strict = strict;
+                graph
+            },
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn test_write_round_trip(graph in arb_graph()) {
+            let text = DagittyWriter::write_str(&graph);
+            let parsed = DagittyParser::parse_str(&text).unwrap();
+            prop_assert!(parsed == graph, "{}", text);
+        }
+    }
+
     #[test]
     fn test_pest_parser() {
         let mut parser = DagittyParser::parse(Rule::dagitty_graph, BASE_DAG_STR).unwrap();
@@ -370,7 +820,7 @@ E -> D
             strict = true;
             pair = dagitty_g.next().unwrap();
         }
-        assert_eq!(false, strict);
+        assert!(!strict);
         assert_eq!(Rule::GRAPHTYPE, pair.as_rule());
         assert_eq!("dag", pair.as_str());
         if let Rule::IDENTIFIER = pair.as_rule() {
@@ -379,11 +829,37 @@ E -> D
         assert_eq!(None, name);
     }
 
+    #[test]
+    fn test_rename_node() {
+        let graph = DagittyParser::parse_str("dag { A -> B }").unwrap();
+        let a = graph.node_by_id("A").unwrap();
+        graph.node_weight(a).unwrap().rename("\"A 1\"");
+        assert_eq!(Some(a), graph.node_by_id("A 1"));
+        assert_eq!(
+            "node_path_id_\"A 1\"",
+            graph.node_weight(a).unwrap().vertex_path_id.get_cloned()
+        );
+
+        let written = DagittyWriter::write_str(&graph);
+        assert!(written.contains("\"A 1\" -> B"));
+        assert_eq!(graph, DagittyParser::parse_str(&written).unwrap());
+    }
+
+    #[test]
+    fn test_is_identifier() {
+        for id in ["A", "_x1", "-1.5", "\"Y 1\"", "<b>", "\"say \\\"hi\\\"\""] {
+            assert!(DagittyParser::is_identifier(id), "{}", id);
+        }
+        for id in ["", "Y 1", "1a", "A-B", "\"open", " A"] {
+            assert!(!DagittyParser::is_identifier(id), "{}", id);
+        }
+    }
+
     #[test]
     fn test_parse_a_list() {
         let mut pairs =
             DagittyParser::parse(Rule::a_list, r#"selected,pos="-2.200,-1.520""#).unwrap();
-        let alist = AList::parse(pairs.next().unwrap()).unwrap();
+        let alist = AList::parse(pairs.next().unwrap());
 
         assert_eq!(
             [("selected", ""), ("pos", "\"-2.200,-1.520\"")],
@@ -395,7 +871,7 @@ E -> D
     fn test_parse_attr_list() {
         let mut pairs =
             DagittyParser::parse(Rule::attr_list, r#"[selected,pos="-2.200,-1.520"]"#).unwrap();
-        let attr_list = AttrList::parse(pairs.next().unwrap()).unwrap();
+        let attr_list = AttrList::parse(pairs.next().unwrap());
 
         assert_eq!(
             [("selected", ""), ("pos", "\"-2.200,-1.520\"")],
@@ -406,6 +882,8 @@ E -> D
     #[test]
     fn test_parse_attr_value_position() {
         let pos = DagittyParser::parse_attr_value_position(r#"-2.200,-1.520"#);
-        assert_eq!((-2.200, -1.520), pos);
+        assert_eq!(Some((-2.200, -1.520)), pos);
+        assert_eq!(None, DagittyParser::parse_attr_value_position("1.5"));
+        assert_eq!(None, DagittyParser::parse_attr_value_position("a,2"));
     }
 }
@@ -0,0 +1,740 @@
+#![allow(missing_docs)]
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::builder::CausalGraphBuilder;
+use crate::error::{Error, ErrorKind, Span};
+use pest::{iterators::Pair, Parser};
+use pest_derive::Parser;
+use why_data::{
+    graph::{
+        dagitty::{identifier, name, EdgeInfo, EdgeType, Mark, NodeInfo, VertexType},
+        CausalEdge, CausalGraph, GraphKind,
+    },
+    types::Point,
+};
+
+/// Graphviz positions are in points, 72 per inch, while model positions are in
+/// inches with the y axis pointing down. Graphviz y is measured up from the
+/// bottom of the bounding box, model y down from its top.
+const POINTS_PER_UNIT: f64 = 72.0;
+
+/// Model length in points, rounded to two decimals without a negative zero.
+fn points(value: f64) -> f64 {
+    (value * POINTS_PER_UNIT * 100.0).round() / 100.0 + 0.0
+}
+
+/// Node attributes written from the vertex type, dropped again when reading it.
+const STYLE_ATTRIBUTES: [&str; 3] = ["class", "style", "fillcolor"];
+
+type Attributes = Vec<(String, String)>;
+
+/// Set or replace attributes, keeping the position of replaced ones.
+fn merge_attributes(attributes: &mut Attributes, new: &[(String, String)]) {
+    for (key, value) in new {
+        match attributes.iter_mut().find(|(k, _)| k == key) {
+            Some(attribute) => attribute.1 = value.clone(),
+            None => attributes.push((key.clone(), value.clone())),
+        }
+    }
+}
+
+fn attribute<'a>(attributes: &'a Attributes, key: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| unquote(v))
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Words DOT reserves in any case, which can only be node ids when quoted.
+const KEYWORDS: [&str; 6] = ["node", "edge", "graph", "digraph", "subgraph", "strict"];
+
+/// DOT form of a node id, which otherwise has the dagitty form.
+fn dot_id(id: &str) -> String {
+    if KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(id)) {
+        format!("\"{}\"", id)
+    } else {
+        id.to_string()
+    }
+}
+
+/// DOT attributes always have a value, flags are written as `true`.
+fn attribute_string(key: &str, value: &str) -> String {
+    if value.is_empty() {
+        format!("{}=true", key)
+    } else {
+        format!("{}={}", key, value)
+    }
+}
+
+fn vertex_type_from_class(class: &str) -> Option<VertexType> {
+    match class {
+        "adjusted" => Some(VertexType::Adjusted),
+        "exposure" => Some(VertexType::Exposure),
+        "outcome" => Some(VertexType::Outcome),
+        "selected" => Some(VertexType::Selected),
+        "unobserved" => Some(VertexType::Unobserved),
+        _ => None,
+    }
+}
+
+/// Mark drawn by an `arrowhead` or `arrowtail` shape.
+fn mark_from_arrow(arrow: &str) -> Mark {
+    match arrow {
+        "none" => Mark::Tail,
+        "odot" | "dot" => Mark::Circle,
+        _ => Mark::Arrow,
+    }
+}
+
+fn arrow_from_mark(mark: Mark) -> &'static str {
+    match mark {
+        Mark::Tail => "none",
+        Mark::Arrow => "normal",
+        Mark::Circle => "odot",
+    }
+}
+
+/// Nodes and edges collected from the statements, before building the graph.
+#[derive(Debug, Default)]
+struct DotStatements {
+    nodes: Vec<(String, Attributes, Span)>,
+    node_map: HashMap<String, usize>,
+    edges: Vec<(String, String, Attributes)>,
+    attributes: Attributes,
+}
+
+impl DotStatements {
+    fn add_node(&mut self, id: &str, defaults: &Attributes, attributes: &Attributes, span: Span) {
+        let index = match self.node_map.get(id) {
+            Some(index) => *index,
+            None => {
+                self.nodes.push((id.to_string(), defaults.clone(), span));
+                self.node_map.insert(id.to_string(), self.nodes.len() - 1);
+                self.nodes.len() - 1
+            }
+        };
+        merge_attributes(&mut self.nodes[index].1, attributes);
+    }
+}
+
+/// Default attributes of the nodes and edges declared in a graph or subgraph.
+#[derive(Clone, Debug, Default)]
+struct Scope {
+    node: Attributes,
+    edge: Attributes,
+}
+
+// Parser
+#[derive(Debug, Parser)]
+#[grammar = "dot/dot.pest"]
+pub struct DotParser;
+
+impl DotParser {
+    fn parse_attr_list(pair: Pair<'_, Rule>) -> Attributes {
+        let mut attributes = Vec::new();
+        for a_list in pair.into_inner() {
+            for attr in a_list.into_inner() {
+                let mut inners = attr.into_inner();
+                let key = inners.next().unwrap().as_str().to_string();
+                let value = inners.next().map(|p| p.as_str()).unwrap_or("");
+                merge_attributes(&mut attributes, &[(key, value.to_string())]);
+            }
+        }
+        attributes
+    }
+
+    /// Node id in the dagitty form, so `A` and `"A"` name the same node.
+    fn node_id(pair: Pair<'_, Rule>) -> String {
+        // Ports only tell where edges are drawn.
+        identifier(&name(pair.into_inner().next().unwrap().as_str()))
+    }
+
+    /// Nodes of an edge endpoint, a single node or all the nodes of a subgraph.
+    fn parse_endpoint(
+        pair: Pair<'_, Rule>,
+        scope: &Scope,
+        statements: &mut DotStatements,
+    ) -> Vec<String> {
+        let inner = pair.into_inner().next().unwrap();
+        match inner.as_rule() {
+            Rule::subgraph => Self::parse_subgraph(inner, scope, statements),
+            Rule::node_id => {
+                let span = Span::from_pest(inner.as_span());
+                let id = Self::node_id(inner);
+                statements.add_node(&id, &scope.node, &Vec::new(), span);
+                vec![id]
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn parse_edge(
+        pair: Pair<'_, Rule>,
+        scope: &Scope,
+        statements: &mut DotStatements,
+    ) -> Vec<String> {
+        let mut endpoints = Vec::new();
+        let mut attributes = scope.edge.clone();
+        for inner in pair.into_inner() {
+            match inner.as_rule() {
+                Rule::endpoint => endpoints.push(Self::parse_endpoint(inner, scope, statements)),
+                Rule::attr_list => merge_attributes(&mut attributes, &Self::parse_attr_list(inner)),
+                _ => (),
+            }
+        }
+        for pair in endpoints.windows(2) {
+            for source in &pair[0] {
+                for target in &pair[1] {
+                    statements
+                        .edges
+                        .push((source.clone(), target.clone(), attributes.clone()));
+                }
+            }
+        }
+        endpoints.concat()
+    }
+
+    fn parse_subgraph(
+        pair: Pair<'_, Rule>,
+        scope: &Scope,
+        statements: &mut DotStatements,
+    ) -> Vec<String> {
+        let stmt_list = pair
+            .into_inner()
+            .find(|p| p.as_rule() == Rule::stmt_list)
+            .unwrap();
+        Self::parse_stmts(stmt_list, &mut scope.clone(), statements, false)
+    }
+
+    /// Collect the statements, returning the nodes they mention.
+    fn parse_stmts(
+        pair: Pair<'_, Rule>,
+        scope: &mut Scope,
+        statements: &mut DotStatements,
+        top_level: bool,
+    ) -> Vec<String> {
+        debug_assert!(
+            Rule::stmt_list == pair.as_rule(),
+            "Input must be a stmt_list rule"
+        );
+        let mut nodes = Vec::new();
+        for stmt in pair.into_inner() {
+            let inner = stmt.into_inner().next().unwrap();
+            match inner.as_rule() {
+                Rule::attr_stmt => {
+                    let mut inners = inner.into_inner();
+                    let target = inners.next().unwrap().as_str().to_lowercase();
+                    let attributes = Self::parse_attr_list(inners.next().unwrap());
+                    match target.as_str() {
+                        "node" => merge_attributes(&mut scope.node, &attributes),
+                        "edge" => merge_attributes(&mut scope.edge, &attributes),
+                        _ if top_level => merge_attributes(&mut statements.attributes, &attributes),
+                        _ => (),
+                    }
+                }
+                Rule::id_eq => {
+                    let mut inners = inner.into_inner();
+                    let key = inners.next().unwrap().as_str().to_string();
+                    let value = inners.next().unwrap().as_str().to_string();
+                    if top_level {
+                        merge_attributes(&mut statements.attributes, &[(key, value)]);
+                    }
+                }
+                Rule::node_stmt => {
+                    let span = Span::from_pest(inner.as_span());
+                    let mut inners = inner.into_inner();
+                    let id = Self::node_id(inners.next().unwrap());
+                    let attributes = inners.next().map(Self::parse_attr_list).unwrap_or_default();
+                    statements.add_node(&id, &scope.node, &attributes, span);
+                    nodes.push(id);
+                }
+                Rule::edge_stmt => nodes.extend(Self::parse_edge(inner, scope, statements)),
+                Rule::subgraph => nodes.extend(Self::parse_subgraph(inner, scope, statements)),
+                _ => unreachable!(),
+            }
+        }
+        nodes
+    }
+
+    /// Position in points, with the y axis pointing up.
+    fn position(value: &str, span: Span) -> Result<Point<f64>, Error> {
+        let error = || {
+            Error::new(
+                ErrorKind::InvalidPosition,
+                format!("invalid position {}, expected \"x,y\"", value),
+                span,
+            )
+        };
+        let pos = unquote(value).trim_end_matches('!');
+        let (x, y) = pos.split_once(',').ok_or_else(error)?;
+        let x = x.trim().parse::<f64>().map_err(|_| error())?;
+        let y = y.trim().parse::<f64>().map_err(|_| error())?;
+        Ok(Point::new(x, y))
+    }
+
+    /// Bounding box in points, `llx,lly,urx,ury`.
+    fn bounding_box(value: &str) -> Option<[f64; 4]> {
+        let values = unquote(value)
+            .split(',')
+            .map(|v| v.trim().parse::<f64>().ok())
+            .collect::<Option<Vec<f64>>>()?;
+        values.try_into().ok()
+    }
+
+    /// Marks at the tail and at the head of an edge from its `dir`, `arrowtail`
+    /// and `arrowhead` attributes.
+    fn edge_marks(attributes: &Attributes, directed: bool) -> (Mark, Mark) {
+        let default_dir = if directed { "forward" } else { "none" };
+        let dir = attribute(attributes, "dir").unwrap_or(default_dir);
+        let tail = attribute(attributes, "arrowtail").map_or(Mark::Arrow, mark_from_arrow);
+        let head = attribute(attributes, "arrowhead").map_or(Mark::Arrow, mark_from_arrow);
+        match dir {
+            "both" => (tail, head),
+            "back" => (tail, Mark::Tail),
+            "none" => (Mark::Tail, Mark::Tail),
+            _ => (Mark::Tail, head),
+        }
+    }
+
+    /// Parse Graphviz DOT format to create a causal graph.
+    ///
+    /// Directed graphs become DAGs, or PDAGs and PAGs when they have undirected
+    /// edges or circle marks. Node `class` gives the vertex type and positions
+    /// are converted from points. Edge splines are not kept.
+    pub fn parse_str(content: &str) -> Result<CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>, Error> {
+        let mut parser = DotParser::parse(Rule::dot_graph, content)?;
+        let mut dot_g = parser.next().unwrap().into_inner();
+        let mut strict = false;
+        let mut pair = dot_g.next().unwrap();
+        if let Rule::STRICT = pair.as_rule() {
+            strict = true;
+            pair = dot_g.next().unwrap();
+        }
+        let directed = pair.as_str().to_lowercase() == "digraph";
+
+        let mut name = None;
+        let mut pair = dot_g.next().unwrap();
+        if let Rule::ID = pair.as_rule() {
+            name = Some(pair.as_str().to_string());
+            pair = dot_g.next().unwrap();
+        }
+
+        let mut statements = DotStatements::default();
+        Self::parse_stmts(pair, &mut Scope::default(), &mut statements, true);
+
+        let mut edges = Vec::new();
+        for (source, target, mut attributes) in statements.edges {
+            let (tail, head) = Self::edge_marks(&attributes, directed);
+            let (edge_type, reversed) = EdgeType::from_marks(tail, head);
+            attributes.retain(|(k, _)| !["dir", "arrowtail", "arrowhead", "pos"].contains(&&k[..]));
+            let mut edge = EdgeInfo::new("", None, edge_type);
+            edge.attributes = attributes;
+            if reversed {
+                edges.push((target, source, edge));
+            } else {
+                edges.push((source, target, edge));
+            }
+        }
+
+        let builder = CausalGraphBuilder::<Arc<NodeInfo>, Arc<EdgeInfo>>::new();
+        let edge_types: Vec<EdgeType> = edges.iter().map(|(_, _, e)| e.edge_type()).collect();
+        let mut builder = if directed {
+            builder.mixed(&edge_types)
+        } else {
+            builder.graph()
+        };
+
+        let mut positions = Vec::new();
+        for (_, attributes, span) in &statements.nodes {
+            let pos = match attributes.iter().find(|(k, _)| k == "pos") {
+                Some((_, value)) => Some(Self::position(value, *span)?),
+                None => None,
+            };
+            positions.push(pos);
+        }
+        // Top of the bounding box, or of the nodes when the graph has none.
+        let bb = attribute(&statements.attributes, "bb").and_then(Self::bounding_box);
+        let top = match bb {
+            Some([_, _, _, ury]) => ury,
+            None => positions
+                .iter()
+                .flatten()
+                .map(|p| p.y())
+                .reduce(f64::max)
+                .unwrap_or(0.0),
+        };
+
+        for ((id, mut attributes, _), pos) in statements.nodes.into_iter().zip(positions) {
+            let pos = pos.map_or(Point::new(0.0, 0.0), |p| {
+                Point::new(p.x() / POINTS_PER_UNIT, (top - p.y()) / POINTS_PER_UNIT)
+            });
+            let vertex_type = attribute(&attributes, "class").and_then(vertex_type_from_class);
+            if vertex_type.is_some() {
+                attributes.retain(|(k, _)| !STYLE_ATTRIBUTES.contains(&&k[..]));
+            }
+            attributes.retain(|(k, _)| k != "pos");
+            let mut node = NodeInfo::new(
+                &id,
+                pos.x(),
+                pos.y(),
+                vertex_type.unwrap_or(VertexType::None),
+            );
+            node.attributes = attributes;
+            builder = builder.add_node(Arc::new(node), &id);
+        }
+        for (source, target, edge) in edges {
+            builder = builder.add_edge(&source, &target, Arc::new(edge));
+        }
+        for (key, value) in statements.attributes {
+            let value = match (key.as_str(), bb) {
+                ("bb", Some([llx, lly, urx, ury])) => format!(
+                    "\"{},0,{},{}\"",
+                    llx / POINTS_PER_UNIT,
+                    urx / POINTS_PER_UNIT,
+                    (ury - lly) / POINTS_PER_UNIT
+                ),
+                _ => value,
+            };
+            builder = builder.add_attribute(&key, &value);
+        }
+
+        let mut graph = builder.build();
+        graph.name = name;
+        graph.strict = strict;
+        Ok(graph)
+    }
+}
+
+// Writer
+#[derive(Debug)]
+pub struct DotWriter;
+
+impl DotWriter {
+    fn style(vertex_type: VertexType) -> &'static str {
+        match vertex_type {
+            VertexType::None => "",
+            VertexType::Adjusted => r#"style=filled,fillcolor=white"#,
+            VertexType::Exposure => r##"style=filled,fillcolor="#bed403""##,
+            VertexType::Outcome => r##"style=filled,fillcolor="#00a2e0""##,
+            VertexType::Selected => r##"style=filled,fillcolor="#aaaaaa""##,
+            VertexType::Unobserved => r#"style=dashed"#,
+        }
+    }
+
+    /// Attributes giving the edge marks, none for the default of the graph type.
+    fn edge_attributes(edge_type: EdgeType, directed: bool) -> Vec<String> {
+        let (tail, head) = edge_type.marks();
+        match (tail, head) {
+            (Mark::Tail, Mark::Arrow) if directed => vec![],
+            (Mark::Tail, Mark::Tail) if !directed => vec![],
+            (Mark::Tail, Mark::Tail) => vec!["dir=none".to_string()],
+            (Mark::Tail, head) => {
+                let mut attributes = vec!["dir=forward".to_string()];
+                if head != Mark::Arrow {
+                    attributes.push(format!("arrowhead={}", arrow_from_mark(head)));
+                }
+                attributes
+            }
+            (tail, head) => {
+                let mut attributes = vec!["dir=both".to_string()];
+                if tail != Mark::Arrow {
+                    attributes.push(format!("arrowtail={}", arrow_from_mark(tail)));
+                }
+                if head != Mark::Arrow {
+                    attributes.push(format!("arrowhead={}", arrow_from_mark(head)));
+                }
+                attributes
+            }
+        }
+    }
+
+    /// Write a causal graph in Graphviz DOT format.
+    ///
+    /// Vertex types are written as `class` with a matching style, and positions
+    /// in points up from the bottom of the bounding box, or of the lowest node
+    /// without one. Edge control points are not written.
+    pub fn write_str(graph: &CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>) -> String {
+        let directed = !matches!(graph.kind, GraphKind::Ungraph(_));
+        let mut result = String::new();
+        if graph.strict {
+            result.push_str("strict ");
+        }
+        result.push_str(if directed { "digraph" } else { "graph" });
+        if let Some(name) = &graph.name {
+            result.push(' ');
+            result.push_str(name);
+        }
+        result.push_str(" {\n");
+
+        // Bottom of the bounding box, or of the nodes when the graph has none.
+        let bottom = match graph.bounding_box() {
+            Some((_, max)) => max.y(),
+            None => graph
+                .node_indices()
+                .into_iter()
+                .map(|n| graph.node_weight(n).unwrap().layout_pos.get().y())
+                .reduce(f64::max)
+                .unwrap_or(0.0),
+        };
+        for (key, value) in &graph.attributes {
+            let value = match (key.as_str(), graph.bounding_box()) {
+                ("bb", Some((min, max))) => format!(
+                    "\"{},0,{},{}\"",
+                    points(min.x()),
+                    points(max.x()),
+                    points(max.y() - min.y())
+                ),
+                _ => value.clone(),
+            };
+            result.push_str(&format!("{}={};\n", key, value));
+        }
+
+        for node_index in graph.node_indices() {
+            let node = graph.node_weight(node_index).unwrap();
+            let vertex_type = node.vertex_type.get();
+            let pos = node.layout_pos.get();
+            let mut items = Vec::new();
+            if vertex_type != VertexType::None {
+                items.push(format!("class={}", vertex_type));
+                items.push(Self::style(vertex_type).to_string());
+            }
+            items.push(format!(
+                "pos=\"{},{}\"",
+                points(pos.x()),
+                points(bottom - pos.y())
+            ));
+            for (key, value) in &node.attributes {
+                if !STYLE_ATTRIBUTES.contains(&&key[..]) || vertex_type == VertexType::None {
+                    items.push(attribute_string(key, value));
+                }
+            }
+            result.push_str(&format!(
+                "{} [{}];\n",
+                dot_id(&node.id.lock_ref()),
+                items.join(",")
+            ));
+        }
+
+        let edgeop = if directed { "->" } else { "--" };
+        for edge_index in graph.edge_indices() {
+            let edge = graph.edge_weight(edge_index).unwrap();
+            let (source, target) = graph.edge_endpoints(edge_index).unwrap();
+            let mut items = Self::edge_attributes(edge.edge_type(), directed);
+            items.extend(
+                edge.attributes
+                    .iter()
+                    .map(|(key, value)| attribute_string(key, value)),
+            );
+            result.push_str(&format!(
+                "{} {} {}",
+                dot_id(&graph.node_weight(source).unwrap().id.lock_ref()),
+                edgeop,
+                dot_id(&graph.node_weight(target).unwrap().id.lock_ref())
+            ));
+            if !items.is_empty() {
+                result.push_str(&format!(" [{}]", items.join(",")));
+            }
+            result.push_str(";\n");
+        }
+
+        result.push('}');
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dagitty::{DagittyParser, DagittyWriter};
+    use why_data::graph::NodeIndex;
+
+    const REPORT_DOT: &str = r#"/* Exported by the reporting pipeline */
+strict digraph "report" {
+    graph [bb="0,0,144,72", rankdir=TB];
+    node [shape=ellipse];
+    # preprocessor line
+    E [class=exposure, pos="0,72!", label=<<b>E</b>>];
+    subgraph cluster_mediators {
+        label="mediators";
+        M1; M2:n;
+    }
+    E -> {M1 M2} -> Y [color=red];
+    U [style=dashed, class="unobserved"];
+    U -> E [dir=both, arrowtail=none];
+    U -> Y [dir=none];
+    Y [class=outcome, pos="144,0"]
+}"#;
+
+    #[test]
+    fn test_parse_dot() {
+        let graph = DotParser::parse_str(REPORT_DOT).unwrap();
+        assert!(graph.strict);
+        assert!(matches!(graph.kind, GraphKind::Pdag(_)));
+        assert_eq!(Some("\"report\"".to_string()), graph.name);
+        assert_eq!(
+            Some((Point::new(0.0, 0.0), Point::new(2.0, 1.0))),
+            graph.bounding_box()
+        );
+        assert_eq!(Some("TB"), graph.attributes.get("rankdir").map(|v| &v[..]));
+
+        let ids: Vec<String> = graph
+            .node_indices()
+            .into_iter()
+            .map(|n| graph.node_weight(n).unwrap().id.get_cloned())
+            .collect();
+        assert_eq!(vec!["E", "M1", "M2", "Y", "U"], ids);
+
+        let e = graph.node_weight(NodeIndex::new(0)).unwrap();
+        assert_eq!(VertexType::Exposure, e.vertex_type.get());
+        assert_eq!(Point::new(0.0, 0.0), e.layout_pos.get());
+        assert_eq!(
+            vec![
+                ("shape".to_string(), "ellipse".to_string()),
+                ("label".to_string(), "<<b>E</b>>".to_string())
+            ],
+            e.attributes
+        );
+        let u = graph.node_weight(NodeIndex::new(4)).unwrap();
+        assert_eq!(VertexType::Unobserved, u.vertex_type.get());
+        assert_eq!(
+            vec![("shape".to_string(), "ellipse".to_string())],
+            u.attributes
+        );
+
+        let edges: Vec<(usize, EdgeType, usize)> = graph
+            .edge_indices()
+            .into_iter()
+            .map(|e| {
+                let (s, t) = graph.edge_endpoints(e).unwrap();
+                (
+                    s.index(),
+                    graph.edge_weight(e).unwrap().edge_type(),
+                    t.index(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (0, EdgeType::Directed, 1),
+                (0, EdgeType::Directed, 2),
+                (1, EdgeType::Directed, 3),
+                (2, EdgeType::Directed, 3),
+                (4, EdgeType::Directed, 0),
+                (4, EdgeType::Undirected, 3),
+            ],
+            edges
+        );
+    }
+
+    #[test]
+    fn test_edge_marks() {
+        let graph = DotParser::parse_str(
+            "digraph { A -> B [dir=both]; A -> C [dir=both,arrowtail=odot]; \
+             C -> D [dir=both,arrowtail=odot,arrowhead=odot]; D -> A [arrowhead=odot] }",
+        )
+        .unwrap();
+        assert!(matches!(graph.kind, GraphKind::Pag(_)));
+        let types: Vec<EdgeType> = graph
+            .edge_indices()
+            .into_iter()
+            .map(|e| graph.edge_weight(e).unwrap().edge_type())
+            .collect();
+        assert_eq!(
+            vec![
+                EdgeType::Bidirected,
+                EdgeType::PartiallyDirected,
+                EdgeType::Nondirected,
+                EdgeType::PartiallyUndirected
+            ],
+            types
+        );
+
+        let graph = DotParser::parse_str("graph { A -- B; B -- C [dir=forward] }").unwrap();
+        assert!(matches!(graph.kind, GraphKind::Ungraph(_)));
+        let text = DotWriter::write_str(&graph);
+        assert!(text.contains("A -- B;"));
+        assert!(text.contains("B -- C [dir=forward];"));
+    }
+
+    #[test]
+    fn test_invalid_position() {
+        let error = DotParser::parse_str("digraph {\n A [pos=\"1\"]\n}").unwrap_err();
+        assert_eq!(ErrorKind::InvalidPosition, error.kind);
+        assert_eq!(2, error.span.line);
+    }
+
+    #[test]
+    fn test_quoted_ids() {
+        let graph = DotParser::parse_str(r#"digraph { "A" -> B; A -> "B"; "C D" -> é }"#).unwrap();
+        let ids: Vec<String> = graph
+            .node_indices()
+            .into_iter()
+            .map(|n| graph.node_weight(n).unwrap().id.get_cloned())
+            .collect();
+        assert_eq!(vec!["A", "B", "\"C D\"", "\"é\""], ids);
+        assert_eq!(3, graph.edge_indices().len());
+
+        let text = DagittyWriter::write_str(&graph);
+        let parsed = DagittyParser::parse_str(&text).unwrap();
+        assert!(parsed == graph, "{}", text);
+    }
+
+    #[test]
+    fn test_keyword_ids() {
+        let graph =
+            DagittyParser::parse_str("dag { node -> edge -> Graph; subgraph -> STRICT }").unwrap();
+        let text = DotWriter::write_str(&graph);
+        assert!(text.contains("\"node\" -> \"edge\";"));
+        assert!(text.contains("\"subgraph\" -> \"STRICT\";"));
+        let parsed = DotParser::parse_str(&text).unwrap();
+        assert!(parsed == graph, "{}", text);
+    }
+
+    #[test]
+    fn test_dot_round_trip() {
+        let graph = DagittyParser::parse_str(
+            r#"pag model {
+bb="-1,0,3,4"
+A [exposure,pos="0.5,-1.25",label="a"]
+B [pos="1,2"]
+C [latent,pos="-1,0"]
+A @-> B [beta=1]
+B <-> C
+C --@ A
+A -- C
+}"#,
+        )
+        .unwrap();
+        let text = DotWriter::write_str(&graph);
+        assert!(text.starts_with("digraph model {\nbb=\"-72,0,216,288\";\n"));
+        assert!(text.contains(
+            r##"A [class=exposure,style=filled,fillcolor="#bed403",pos="36,378",label="a"];"##
+        ));
+        assert!(text.contains("A -> B [dir=both,arrowtail=odot,beta=1];"));
+        assert!(text.contains("A -> C [dir=none];"));
+
+        let parsed = DotParser::parse_str(&text).unwrap();
+        assert!(parsed == graph, "{}", text);
+
+        // Without a bounding box y is measured from the lowest node.
+        let graph = DagittyParser::parse_str(r#"dag { A [pos="0,0"] B [pos="0.5,0.3"] }"#).unwrap();
+        let text = DotWriter::write_str(&graph);
+        assert!(text.contains(r#"A [pos="0,21.6"];"#), "{}", text);
+        assert!(text.contains(r#"B [pos="36,0"];"#), "{}", text);
+        let parsed = DotParser::parse_str(&text).unwrap();
+        for n in graph.node_indices() {
+            let pos = graph.node_weight(n).unwrap().layout_pos.get();
+            let parsed_pos = parsed.node_weight(n).unwrap().layout_pos.get();
+            assert!((pos.x() - parsed_pos.x()).abs() < 1e-9);
+            assert!((pos.y() - parsed_pos.y()).abs() < 1e-9);
+        }
+    }
+}
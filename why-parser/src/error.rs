@@ -0,0 +1,162 @@
+use std::fmt::Display;
+
+use pest::error::{InputLocation, LineColLocation};
+use pest::RuleType;
+
+/// Location of a piece of the parsed input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    /// Byte offset where the span starts.
+    pub start: usize,
+    /// Byte offset where the span ends.
+    pub end: usize,
+    /// Line where the span starts, starting at 1.
+    pub line: usize,
+    /// Column where the span starts, starting at 1.
+    pub column: usize,
+}
+
+impl Span {
+    pub(crate) fn from_pest(span: pest::Span<'_>) -> Self {
+        let (line, column) = span.start_pos().line_col();
+        Self {
+            start: span.start(),
+            end: span.end(),
+            line,
+            column,
+        }
+    }
+
+    /// Span of the bytes `start..end` of `content`.
+    pub(crate) fn at(content: &str, start: usize, end: usize) -> Self {
+        let before = &content[..start];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            start,
+            end,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Kind of a parse error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The input doesn't follow the grammar.
+    Syntax,
+    /// A `pos` attribute isn't a pair of numbers.
+    InvalidPosition,
+    /// An adjacency matrix isn't square or has an unknown endpoint code.
+    InvalidMatrix,
+    /// A node is declared more than once.
+    DuplicateNode,
+    /// A node is used without being declared.
+    UndeclaredNode,
+    /// An edge is given more than once.
+    DuplicateEdge,
+    /// Directed edges form a cycle in a DAG.
+    Cycle,
+}
+
+/// Error parsing a causal graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    /// Kind of error.
+    pub kind: ErrorKind,
+    /// Human readable message.
+    pub message: String,
+    /// Location of the error.
+    pub span: Span,
+    /// Warnings found before the error.
+    pub warnings: Vec<Warning>,
+}
+
+impl Error {
+    pub(crate) fn new(kind: ErrorKind, message: String, span: Span) -> Self {
+        Self {
+            kind,
+            message,
+            span,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl<R: RuleType> From<pest::error::Error<R>> for Error {
+    fn from(error: pest::error::Error<R>) -> Self {
+        let (start, end) = match error.location {
+            InputLocation::Pos(pos) => (pos, pos),
+            InputLocation::Span(span) => span,
+        };
+        let (line, column) = match error.line_col {
+            LineColLocation::Pos(pos) => pos,
+            LineColLocation::Span(pos, _) => pos,
+        };
+        let span = Span {
+            start,
+            end,
+            line,
+            column,
+        };
+        Self::new(
+            ErrorKind::Syntax,
+            error.variant.message().into_owned(),
+            span,
+        )
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Kind of a parse warning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WarningKind {
+    /// Node or edge attribute the parser doesn't interpret.
+    UnknownAttribute,
+    /// Node declared more than once.
+    DuplicateNode,
+    /// Edge referencing a node that is never declared.
+    UndeclaredNode,
+    /// Directed edge closing a cycle in a `dag`.
+    Cycle,
+}
+
+/// Problem found in a graph that parses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+    /// Kind of warning.
+    pub kind: WarningKind,
+    /// Human readable message.
+    pub message: String,
+    /// Location of the warning.
+    pub span: Span,
+}
+
+impl Warning {
+    pub(crate) fn new(kind: WarningKind, message: String, span: Span) -> Self {
+        Self {
+            kind,
+            message,
+            span,
+        }
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: warning: {}", self.span, self.message)
+    }
+}
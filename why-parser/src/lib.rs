@@ -8,5 +8,18 @@
 
 //! Parser for dagitty format.
 
+/// bnlearn model strings.
+pub mod bnlearn;
+mod builder;
 /// Parsing module.
 pub mod dagitty;
+/// Graphviz DOT format.
+pub mod dot;
+/// Parse errors and warnings.
+pub mod error;
+/// pcalg adjacency matrices.
+pub mod pcalg;
+/// TikZ pictures for LaTeX documents.
+pub mod tikz;
+
+pub use error::{Error, Warning};
@@ -0,0 +1,415 @@
+#![allow(missing_docs)]
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::builder::CausalGraphBuilder;
+use crate::error::{Error, ErrorKind, Span};
+use why_data::graph::{
+    dagitty::{identifier, name, EdgeInfo, EdgeType, Mark, NodeInfo, VertexType},
+    CausalEdge, CausalGraph,
+};
+
+/// Endpoint coding of a pcalg adjacency matrix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AmatType {
+    /// `amat.cpdag` coding, also used for DAGs: `amat[a, b] = 0` and
+    /// `amat[b, a] = 1` mean `a -> b`, both entries 1 mean `a -- b`.
+    Cpdag,
+    /// `amat.pag` coding: `amat[a, b]` is the mark at `b` of the edge between
+    /// `a` and `b`, 0 for no edge, 1 circle, 2 arrowhead and 3 tail.
+    Pag,
+}
+
+type Cell = (String, Span);
+
+/// Split a line in cells separated by commas, or by blanks when it has none.
+/// Cells may be quoted, with `""` standing for a quote.
+fn cells(content: &str, offset: usize, line: &str) -> Vec<Cell> {
+    let comma = line.contains(',');
+    let separator = |c: char| {
+        if comma {
+            c == ','
+        } else {
+            c == ' ' || c == '\t'
+        }
+    };
+    let mut cells = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    loop {
+        while chars.next_if(|&(_, c)| c == ' ' || c == '\t').is_some() {}
+        let start = chars.peek().map_or(line.len(), |&(i, _)| i);
+        if !comma && start == line.len() {
+            break;
+        }
+        let mut value = String::new();
+        if chars.next_if(|&(_, c)| c == '"').is_some() {
+            while let Some((_, c)) = chars.next() {
+                if c != '"' {
+                    value.push(c);
+                } else if chars.next_if(|&(_, c)| c == '"').is_some() {
+                    value.push('"');
+                } else {
+                    break;
+                }
+            }
+        }
+        while let Some((_, c)) = chars.next_if(|&(_, c)| !separator(c)) {
+            value.push(c);
+        }
+        let end = chars.peek().map_or(line.len(), |&(i, _)| i);
+        cells.push((
+            value.trim_end().to_string(),
+            Span::at(content, offset + start, offset + end),
+        ));
+        if chars.next().is_none() {
+            break;
+        }
+    }
+    cells
+}
+
+fn mark_from_code(code: u8) -> Mark {
+    match code {
+        1 => Mark::Circle,
+        2 => Mark::Arrow,
+        _ => Mark::Tail,
+    }
+}
+
+fn code_from_mark(mark: Mark) -> u8 {
+    match mark {
+        Mark::Circle => 1,
+        Mark::Arrow => 2,
+        Mark::Tail => 3,
+    }
+}
+
+/// Quote a name the way R's `write.csv` does.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// Parser
+#[derive(Debug)]
+pub struct AmatParser;
+
+impl AmatParser {
+    fn invalid(message: String, span: Span) -> Error {
+        Error::new(ErrorKind::InvalidMatrix, message, span)
+    }
+
+    /// Node names from the header, the row names or `V1` to `Vn`, and the
+    /// codes of every row.
+    #[allow(clippy::type_complexity)]
+    fn matrix(
+        content: &str,
+        amat_type: AmatType,
+    ) -> Result<(Vec<Cell>, Vec<Vec<(u8, Span)>>), Error> {
+        let mut rows = Vec::new();
+        let mut offset = 0;
+        for line in content.split_inclusive('\n') {
+            let text = line.trim_end_matches(['\n', '\r']);
+            if !text.trim().is_empty() && !text.trim_start().starts_with('#') {
+                rows.push(cells(content, offset, text));
+            }
+            offset += line.len();
+        }
+
+        let header = match rows.first() {
+            Some(first) if first.iter().any(|(value, _)| value.parse::<u8>().is_err()) => {
+                Some(rows.remove(0))
+            }
+            _ => None,
+        };
+        let n = rows.len();
+        let max_code = match amat_type {
+            AmatType::Cpdag => 1,
+            AmatType::Pag => 3,
+        };
+
+        let mut row_names = Vec::new();
+        let mut codes = Vec::new();
+        for mut row in rows {
+            if row.len() == n + 1 {
+                row_names.push(row.remove(0));
+            } else if row.len() != n {
+                return Err(Self::invalid(
+                    format!("expected {} entries in a row, found {}", n, row.len()),
+                    row[0].1,
+                ));
+            }
+            let mut row_codes = Vec::new();
+            for (value, span) in row {
+                match value.parse::<u8>() {
+                    Ok(code) if code <= max_code => row_codes.push((code, span)),
+                    _ => {
+                        return Err(Self::invalid(
+                            format!("unknown endpoint code `{}`", value),
+                            span,
+                        ))
+                    }
+                }
+            }
+            codes.push(row_codes);
+        }
+
+        let names = match header {
+            Some(mut header) => {
+                if header.len() == n + 1 {
+                    header.remove(0);
+                } else if header.len() != n {
+                    return Err(Self::invalid(
+                        format!("expected {} names in the header, found {}", n, header.len()),
+                        header[0].1,
+                    ));
+                }
+                header
+            }
+            None if row_names.len() == n && n > 0 => row_names,
+            None => {
+                let span = Span::at(content, 0, 0);
+                (1..=n).map(|i| (format!("V{}", i), span)).collect()
+            }
+        };
+        Ok((names, codes))
+    }
+
+    /// Parse a pcalg adjacency matrix as written by R's `write.csv` or
+    /// `write.table`, or printed.
+    ///
+    /// Node names come from the header or the row names, `V1` to `Vn` when
+    /// there are none. Nodes are placed in causal order.
+    pub fn parse_str(
+        content: &str,
+        amat_type: AmatType,
+    ) -> Result<CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>, Error> {
+        let (names, codes) = Self::matrix(content, amat_type)?;
+
+        let mut seen = HashSet::new();
+        for (name, span) in &names {
+            if !seen.insert(name) {
+                return Err(Error::new(
+                    ErrorKind::DuplicateNode,
+                    format!("node {} is declared more than once", name),
+                    *span,
+                ));
+            }
+        }
+        let ids: Vec<String> = names.iter().map(|(name, _)| identifier(name)).collect();
+
+        let mut edges = Vec::new();
+        for (i, row) in codes.iter().enumerate() {
+            let (code, span) = row[i];
+            if code != 0 {
+                return Err(Self::invalid(format!("self loop at {}", names[i].0), span));
+            }
+            for j in i + 1..codes.len() {
+                let (at_j, span) = row[j];
+                let (at_i, _) = codes[j][i];
+                let edge_type = match (amat_type, at_i, at_j) {
+                    (_, 0, 0) => continue,
+                    (AmatType::Cpdag, 1, 0) => Some((EdgeType::Directed, false)),
+                    (AmatType::Cpdag, 0, 1) => Some((EdgeType::Directed, true)),
+                    (AmatType::Cpdag, _, _) => Some((EdgeType::Undirected, false)),
+                    (AmatType::Pag, 0, _) | (AmatType::Pag, _, 0) => None,
+                    (AmatType::Pag, _, _) => Some(EdgeType::from_marks(
+                        mark_from_code(at_i),
+                        mark_from_code(at_j),
+                    )),
+                };
+                match edge_type {
+                    Some((edge_type, false)) => edges.push((i, j, edge_type)),
+                    Some((edge_type, true)) => edges.push((j, i, edge_type)),
+                    None => {
+                        return Err(Self::invalid(
+                            format!(
+                                "edge between {} and {} has a single endpoint",
+                                names[i].0, names[j].0
+                            ),
+                            span,
+                        ))
+                    }
+                }
+            }
+        }
+
+        let builder = CausalGraphBuilder::<Arc<NodeInfo>, Arc<EdgeInfo>>::new();
+        let mut builder = match amat_type {
+            AmatType::Cpdag => {
+                let edge_types: Vec<EdgeType> = edges.iter().map(|(_, _, e)| *e).collect();
+                builder.mixed(&edge_types)
+            }
+            AmatType::Pag => builder.pag(),
+        };
+        for id in &ids {
+            builder = builder.add_node(Arc::new(NodeInfo::new(id, 0.0, 0.0, VertexType::None)), id);
+        }
+        for (source, target, edge_type) in edges {
+            let edge = EdgeInfo::new("", None, edge_type);
+            builder = builder.add_edge(&ids[source], &ids[target], Arc::new(edge));
+        }
+
+        let graph = builder.build();
+        graph.layout_layers();
+        Ok(graph)
+    }
+}
+
+// Writer
+#[derive(Debug)]
+pub struct AmatWriter;
+
+impl AmatWriter {
+    /// Write a causal graph as a pcalg adjacency matrix in R's `write.csv`
+    /// format, `None` if the coding can't represent some edge.
+    pub fn write_str(
+        graph: &CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>,
+        amat_type: AmatType,
+    ) -> Option<String> {
+        let nodes = graph.node_indices();
+        let position: HashMap<_, _> = nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+        let mut amat = vec![vec![0; nodes.len()]; nodes.len()];
+        for edge_index in graph.edge_indices() {
+            let (source, target) = graph.edge_endpoints(edge_index).unwrap();
+            let (i, j) = (position[&source], position[&target]);
+            let (tail, head) = graph.edge_weight(edge_index).unwrap().edge_type().marks();
+            match (amat_type, tail, head) {
+                (AmatType::Pag, _, _) => {
+                    amat[i][j] = code_from_mark(head);
+                    amat[j][i] = code_from_mark(tail);
+                }
+                (AmatType::Cpdag, Mark::Tail, Mark::Arrow) => amat[j][i] = 1,
+                (AmatType::Cpdag, Mark::Tail, Mark::Tail) => {
+                    amat[i][j] = 1;
+                    amat[j][i] = 1;
+                }
+                (AmatType::Cpdag, _, _) => return None,
+            }
+        }
+
+        let names: Vec<String> = nodes
+            .iter()
+            .map(|n| quote(&name(&graph.node_weight(*n).unwrap().id.lock_ref())))
+            .collect();
+        let mut result = format!("\"\",{}\n", names.join(","));
+        for (name, row) in names.iter().zip(amat) {
+            let row: Vec<String> = row.iter().map(|code| code.to_string()).collect();
+            result.push_str(&format!("{},{}\n", name, row.join(",")));
+        }
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use why_data::graph::{GraphKind, NodeIndex};
+
+    fn edges(graph: &CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>) -> Vec<(usize, EdgeType, usize)> {
+        graph
+            .edge_indices()
+            .into_iter()
+            .map(|e| {
+                let (s, t) = graph.edge_endpoints(e).unwrap();
+                (
+                    s.index(),
+                    graph.edge_weight(e).unwrap().edge_type(),
+                    t.index(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_cpdag() {
+        let csv = "\"\",\"A\",\"B\",\"C\"\n\"A\",0,1,0\n\"B\",1,0,0\n\"C\",1,1,0\n";
+        let graph = AmatParser::parse_str(csv, AmatType::Cpdag).unwrap();
+        assert!(matches!(graph.kind, GraphKind::Pdag(_)));
+        let ids: Vec<String> = graph
+            .node_indices()
+            .iter()
+            .map(|n| graph.node_weight(*n).unwrap().id.get_cloned())
+            .collect();
+        assert_eq!(ids, vec!["A", "B", "C"]);
+        assert_eq!(
+            edges(&graph),
+            vec![
+                (0, EdgeType::Undirected, 1),
+                (0, EdgeType::Directed, 2),
+                (1, EdgeType::Directed, 2)
+            ]
+        );
+        // A is first in causal order, C below it.
+        let a = graph.node_weight(NodeIndex::new(0)).unwrap();
+        let c = graph.node_weight(NodeIndex::new(2)).unwrap();
+        assert!(a.layout_pos.get().y() < c.layout_pos.get().y());
+
+        // Printed matrix without the leading header cell.
+        let printed = "  A B\nA 0 0\nB 1 0\n";
+        let graph = AmatParser::parse_str(printed, AmatType::Cpdag).unwrap();
+        assert!(matches!(graph.kind, GraphKind::Dag(_)));
+        assert_eq!(edges(&graph), vec![(0, EdgeType::Directed, 1)]);
+
+        // Without names.
+        let graph = AmatParser::parse_str("0 0\n1 0\n", AmatType::Cpdag).unwrap();
+        assert_eq!(
+            graph
+                .node_weight(NodeIndex::new(1))
+                .unwrap()
+                .id
+                .get_cloned(),
+            "V2"
+        );
+    }
+
+    #[test]
+    fn test_parse_pag() {
+        let csv = "\"\",\"x 1\",\"y\",\"z\"\n\"x 1\",0,2,0\n\"y\",1,0,3\n\"z\",0,2,0\n";
+        let graph = AmatParser::parse_str(csv, AmatType::Pag).unwrap();
+        assert!(matches!(graph.kind, GraphKind::Pag(_)));
+        assert_eq!(
+            graph
+                .node_weight(NodeIndex::new(0))
+                .unwrap()
+                .id
+                .get_cloned(),
+            "\"x 1\""
+        );
+        assert_eq!(
+            edges(&graph),
+            vec![
+                (0, EdgeType::PartiallyDirected, 1),
+                (2, EdgeType::Directed, 1)
+            ]
+        );
+        assert_eq!(AmatWriter::write_str(&graph, AmatType::Pag).unwrap(), csv);
+        assert_eq!(AmatWriter::write_str(&graph, AmatType::Cpdag), None);
+    }
+
+    #[test]
+    fn test_invalid_matrix() {
+        let error = AmatParser::parse_str("A,B\n0,1\n0,5\n", AmatType::Pag).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidMatrix);
+        assert_eq!((error.span.line, error.span.column), (3, 3));
+
+        let error = AmatParser::parse_str("A,B\n0,1\n", AmatType::Pag).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidMatrix);
+
+        let error = AmatParser::parse_str("A,B\n0,2\n0,0\n", AmatType::Pag).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidMatrix);
+        assert_eq!(error.span.line, 2);
+
+        let error = AmatParser::parse_str("A,A\n0,0\n0,0\n", AmatType::Cpdag).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::DuplicateNode);
+    }
+
+    #[test]
+    fn test_cpdag_round_trip() {
+        let csv = "\"\",\"A\",\"B\",\"C\"\n\"A\",0,1,0\n\"B\",1,0,0\n\"C\",1,1,0\n";
+        let graph = AmatParser::parse_str(csv, AmatType::Cpdag).unwrap();
+        assert_eq!(AmatWriter::write_str(&graph, AmatType::Cpdag).unwrap(), csv);
+    }
+}
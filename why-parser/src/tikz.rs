@@ -0,0 +1,131 @@
+#![allow(missing_docs)]
+
+use std::sync::Arc;
+
+use why_data::graph::{
+    dagitty::{name, EdgeInfo, Mark, NodeInfo, VertexType},
+    CausalEdge, CausalGraph,
+};
+
+/// Styles of the vertex types, with the colours of the dagitty and DOT output.
+const STYLES: &str = r"every node/.style={draw,rounded corners},
+    exposure/.style={fill={rgb,255:red,190;green,212;blue,3}},
+    outcome/.style={fill={rgb,255:red,0;green,162;blue,224}},
+    adjusted/.style={fill=white,double},
+    selected/.style={fill={rgb,255:red,170;green,170;blue,170}},
+    unobserved/.style={dashed}";
+
+/// Escape the characters LaTeX treats specially.
+fn escape(text: &str) -> String {
+    let mut result = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => result.push_str(r"\textbackslash{}"),
+            '~' => result.push_str(r"\textasciitilde{}"),
+            '^' => result.push_str(r"\textasciicircum{}"),
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                result.push('\\');
+                result.push(c);
+            }
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+fn arrow_tip(mark: Mark) -> &'static str {
+    match mark {
+        Mark::Tail => "",
+        Mark::Arrow => "Stealth",
+        Mark::Circle => "{Circle[open]}",
+    }
+}
+
+// Writer
+#[derive(Debug)]
+pub struct TikzWriter;
+
+impl TikzWriter {
+    /// Write a causal graph as a TikZ picture, for LaTeX documents loading the
+    /// `arrows.meta` library.
+    ///
+    /// Model units are 2cm with the y axis pointing down, and edge positions are
+    /// used as control points.
+    pub fn write_str(graph: &CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>) -> String {
+        let mut result = format!(
+            "% \\usetikzlibrary{{arrows.meta}}\n\\begin{{tikzpicture}}[x=2cm,y=-2cm,\n    {}]\n",
+            STYLES
+        );
+
+        for node_index in graph.node_indices() {
+            let node = graph.node_weight(node_index).unwrap();
+            let pos = node.layout_pos.get();
+            let style = match node.vertex_type.get() {
+                VertexType::None => String::new(),
+                vertex_type => format!("[{}]", vertex_type),
+            };
+            result.push_str(&format!(
+                "  \\node{} (n{}) at ({},{}) {{{}}};\n",
+                style,
+                node_index.index(),
+                pos.x(),
+                pos.y(),
+                escape(&name(&node.id.lock_ref()))
+            ));
+        }
+
+        for edge_index in graph.edge_indices() {
+            let edge = graph.edge_weight(edge_index).unwrap();
+            let (source, target) = graph.edge_endpoints(edge_index).unwrap();
+            let (tail, head) = edge.edge_type().marks();
+            let path = match edge.layout_pos.get() {
+                Some(pos) => format!(".. controls ({},{}) ..", pos.x(), pos.y()),
+                None => "to".to_string(),
+            };
+            result.push_str(&format!(
+                "  \\draw[{}-{}] (n{}) {} (n{});\n",
+                arrow_tip(tail),
+                arrow_tip(head),
+                source.index(),
+                path,
+                target.index()
+            ));
+        }
+
+        result.push_str("\\end{tikzpicture}\n");
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dagitty::DagittyParser;
+
+    #[test]
+    fn test_write_tikz() {
+        let graph = DagittyParser::parse_str(
+            r#"pag {
+                E [exposure,pos="0,0"]
+                "Y_1" [outcome,pos="1,0.5"]
+                U [pos="0.5,-1"]
+                E -> "Y_1" [pos="0.5,0"]
+                U @-@ E
+            }"#,
+        )
+        .unwrap();
+        let tikz = TikzWriter::write_str(&graph);
+        let lines: Vec<&str> = tikz.lines().skip(8).collect();
+        assert_eq!(
+            lines,
+            vec![
+                r"  \node[exposure] (n0) at (0,0) {E};",
+                r"  \node[outcome] (n1) at (1,0.5) {Y\_1};",
+                r"  \node (n2) at (0.5,-1) {U};",
+                r"  \draw[-Stealth] (n0) .. controls (0.5,0) .. (n1);",
+                r"  \draw[{Circle[open]}-{Circle[open]}] (n2) to (n0);",
+                r"\end{tikzpicture}",
+            ]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use crate::section_header::SectionHeader;
+use crate::svggraph::SvgGraph;
+use dominator::{clone, html, Dom};
+use futures_signals::signal::{Mutable, SignalExt};
+use std::sync::Arc;
+use why_data::graph::adjustment::AdjustmentCheck;
+
+pub struct AdjustmentSection {
+    header: Arc<SectionHeader>,
+    displayed: Mutable<bool>,
+}
+
+impl AdjustmentSection {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            header: SectionHeader::new(
+                " Causal effect identification".to_string(),
+                "adjustment".to_string(),
+            ),
+            displayed: Mutable::new(true),
+        })
+    }
+
+    fn div(svg_graph: &Arc<SvgGraph>, check: &Option<AdjustmentCheck>) -> Dom {
+        let check = match check {
+            Some(check) => check,
+            None => {
+                return html!("p", {
+                    .text("Define an exposure and an outcome to check the adjustment.")
+                })
+            }
+        };
+
+        let mut children = vec![html!("p", {
+            .text(if check.valid {
+                "The adjusted variables are a sufficient adjustment set for the total effect."
+            } else {
+                "The adjusted variables are not a sufficient adjustment set for the total effect."
+            })
+        })];
+
+        if !check.descendants.is_empty() {
+            children.push(html!("p", {
+                .text(&format!(
+                    "Adjusted descendants of the exposure: {}",
+                    svg_graph.graph.lock_ref().nodes_to_string(&check.descendants)
+                ))
+            }));
+        }
+
+        if !check.biasing_paths.is_empty() {
+            children.push(html!("p", {
+                .text("Open biasing paths:")
+            }));
+            children.push(html!("ul", {
+                .children(check.biasing_paths.iter().map(|path| {
+                    html!("li", {
+                        .text(&svg_graph.graph.lock_ref().path_to_string(path))
+                    })
+                }))
+            }));
+        }
+
+        html!("div", {
+            .children(children)
+        })
+    }
+
+    pub fn render(this: &Arc<Self>, svg_graph: &Arc<SvgGraph>) -> Dom {
+        html!("section", {
+            .child(SectionHeader::render(&this.header, &this.displayed))
+            .child(html!("div", {
+                .visible_signal(this.displayed.signal())
+                .child_signal(svg_graph.adjustment.signal_cloned().map(
+                    clone!(svg_graph => move |check| {
+                    Some(Self::div(&svg_graph, &check))
+                })))
+            }))
+        })
+    }
+}
@@ -2,14 +2,17 @@ use std::sync::Arc;
 
 use dominator::{clone, events, html, with_node, Dom};
 use futures_signals::signal::SignalExt;
-use web_sys::HtmlElement;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlElement, HtmlInputElement, HtmlTextAreaElement};
 use why_parser::dagitty::DagittyParser;
 
+use crate::adjustment_section::AdjustmentSection;
 use crate::bounds::{Bounds, ContainerCoordinates};
 use crate::css::{
     BODY_CLASS, LEFT_LEGEND_DIV_CLASS, MAIN_CLASS, MENU_DIV_CLASS, RIGHT_LEGEND_DIV_CLASS,
     SVG_DIV_CLASS,
 };
+use crate::implications_section::ImplicationsSection;
 use crate::model_data_section::ModelDataSection;
 use crate::svggraph::{SvgGraph, DEFAULT_GRAPH};
 use crate::variable_section::VariableSection;
@@ -32,17 +35,21 @@ impl App {
 
     fn left_side_tag(this: &Arc<Self>) -> Dom {
         let variable_section = VariableSection::new();
+        let adjustment_section = AdjustmentSection::new();
         html!("div", {
             .class(&*LEFT_LEGEND_DIV_CLASS)
             .child(VariableSection::render(&variable_section, &this.svg_graph))
+            .child(AdjustmentSection::render(&adjustment_section, &this.svg_graph))
         })
     }
 
     fn right_side_tag(this: &Arc<Self>) -> Dom {
         let model_data_section = ModelDataSection::new();
+        let implications_section = ImplicationsSection::new();
         html!("div", {
             .class(&*RIGHT_LEGEND_DIV_CLASS)
             .child(ModelDataSection::render(&model_data_section, &this.svg_graph))
+            .child(ImplicationsSection::render(&implications_section, &this.svg_graph))
         })
     }
 
@@ -97,9 +104,28 @@ impl App {
         })
     }
 
+    fn key_down(this: &Arc<Self>, e: &events::KeyDown) {
+        // Text fields keep their own undo.
+        let editing_text = e.target().is_some_and(|target| {
+            target.has_type::<HtmlTextAreaElement>() || target.has_type::<HtmlInputElement>()
+        });
+        if editing_text || !e.ctrl_key() || !e.key().eq_ignore_ascii_case("z") {
+            return;
+        }
+        e.prevent_default();
+        if e.shift_key() {
+            this.svg_graph.redo();
+        } else {
+            this.svg_graph.undo();
+        }
+    }
+
     pub fn render(this: &Arc<Self>) -> Dom {
         html!("body", {
             .class(&*BODY_CLASS)
+            .global_event(clone!(this => move |e: events::KeyDown| {
+                Self::key_down(&this, &e);
+            }))
             .children(&mut [
                 Self::main_tag(&this.clone()),
                 Self::aside_tag(this),
@@ -81,6 +81,14 @@ pub(crate) static TEXTAREA_CLASS: Lazy<String> = Lazy::new(|| {
     }
 });
 
+pub(crate) static ERROR_CLASS: Lazy<String> = Lazy::new(|| {
+    class! {
+        .style("color", "#c0004d")
+        .style("font-family", "monospace")
+        .style("white-space", "pre-wrap")
+    }
+});
+
 pub(crate) static PATH_CLASS: Lazy<String> = Lazy::new(|| {
     class! {}
 });
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use why_data::{
+    graph::{
+        dagitty::{EdgeInfo, NodeInfo, VertexType},
+        CausalGraph,
+    },
+    types::Point,
+};
+
+use crate::svggraph::SvgGraph;
+
+const MAX_EDITS: usize = 100;
+
+/// Edge with its endpoints, to add it back after the endpoints were removed and
+/// added again under other indices.
+#[derive(Clone, Debug)]
+pub(crate) struct EdgeRecord {
+    pub(crate) source: Arc<NodeInfo>,
+    pub(crate) target: Arc<NodeInfo>,
+    pub(crate) info: Arc<EdgeInfo>,
+}
+
+/// Change of the graph. Nodes and edges are kept by their data rather than their
+/// indices, which change when a removal is undone.
+#[derive(Debug)]
+pub(crate) enum Edit {
+    MoveVertex {
+        node: Arc<NodeInfo>,
+        pos: Point<f64>,
+    },
+    MoveEdge {
+        edge: Arc<EdgeInfo>,
+        pos: Option<Point<f64>>,
+    },
+    VertexType {
+        node: Arc<NodeInfo>,
+        vertex_type: VertexType,
+    },
+    Rename {
+        node: Arc<NodeInfo>,
+        id: String,
+    },
+    AddVertex {
+        node: Arc<NodeInfo>,
+        edges: Vec<EdgeRecord>,
+    },
+    RemoveVertex(Arc<NodeInfo>),
+    AddEdge(EdgeRecord),
+    RemoveEdge(Arc<EdgeInfo>),
+    Model(Box<CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>>),
+}
+
+impl Edit {
+    /// Make the change, returning the edit that reverts it, or `None` when the
+    /// node or edge changed is no longer in the graph.
+    pub(crate) fn apply(self, svg_graph: &SvgGraph) -> Option<Edit> {
+        let inverse = match self {
+            Edit::MoveVertex { node, pos } => {
+                let pos = node.layout_pos.replace(pos);
+                Edit::MoveVertex { node, pos }
+            }
+            Edit::MoveEdge { edge, pos } => {
+                let pos = edge.layout_pos.replace(pos);
+                Edit::MoveEdge { edge, pos }
+            }
+            Edit::VertexType { node, vertex_type } => {
+                let vertex_type = node.vertex_type.replace(vertex_type);
+                Edit::VertexType { node, vertex_type }
+            }
+            Edit::Rename { node, id } => {
+                let old = node.id.get_cloned();
+                node.rename(&id);
+                Edit::Rename { node, id: old }
+            }
+            Edit::AddVertex { node, edges } => {
+                svg_graph.insert_vertex(node.clone());
+                for edge in edges {
+                    svg_graph.insert_edge(&edge);
+                }
+                Edit::RemoveVertex(node)
+            }
+            Edit::RemoveVertex(node) => {
+                let edges = svg_graph.delete_vertex(&node)?;
+                Edit::AddVertex { node, edges }
+            }
+            Edit::AddEdge(edge) => {
+                svg_graph.insert_edge(&edge)?;
+                Edit::RemoveEdge(edge.info)
+            }
+            Edit::RemoveEdge(edge) => Edit::AddEdge(svg_graph.delete_edge(&edge)?),
+            Edit::Model(graph) => Edit::Model(Box::new(svg_graph.replace_graph(*graph))),
+        };
+        Some(inverse)
+    }
+}
+
+/// Edits to undo and redo, most recent last.
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl History {
+    /// Remember how to revert a new change, which can't be redone anymore.
+    pub(crate) fn record(&mut self, undo: Edit) {
+        self.redo.clear();
+        self.undo.push(undo);
+        if self.undo.len() > MAX_EDITS {
+            self.undo.remove(0);
+        }
+    }
+
+    pub(crate) fn pop_undo(&mut self) -> Option<Edit> {
+        self.undo.pop()
+    }
+
+    pub(crate) fn pop_redo(&mut self) -> Option<Edit> {
+        self.redo.pop()
+    }
+
+    pub(crate) fn push_undo(&mut self, edit: Edit) {
+        self.undo.push(edit);
+    }
+
+    pub(crate) fn push_redo(&mut self, edit: Edit) {
+        self.redo.push(edit);
+    }
+}
@@ -0,0 +1,57 @@
+use crate::section_header::SectionHeader;
+use crate::svggraph::SvgGraph;
+use dominator::{html, Dom};
+use futures_signals::signal::{Mutable, SignalExt};
+use std::sync::Arc;
+
+pub struct ImplicationsSection {
+    header: Arc<SectionHeader>,
+    displayed: Mutable<bool>,
+}
+
+impl ImplicationsSection {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            header: SectionHeader::new(
+                " Testable implications".to_string(),
+                "implications".to_string(),
+            ),
+            displayed: Mutable::new(true),
+        })
+    }
+
+    fn div(implications: &[String]) -> Dom {
+        if implications.is_empty() {
+            return html!("p", {
+                .text("The model implies no conditional independencies.")
+            });
+        }
+
+        html!("div", {
+            .child(html!("p", {
+                .text("The model implies the following conditional independencies:")
+            }))
+            .child(html!("ul", {
+                .children(implications.iter().map(|i| {
+                    html!("li", {
+                        .style("font-family", "monospace")
+                        .text(i)
+                    })
+                }))
+            }))
+        })
+    }
+
+    pub fn render(this: &Arc<Self>, svg_graph: &Arc<SvgGraph>) -> Dom {
+        html!("section", {
+            .child(SectionHeader::render(&this.header, &this.displayed))
+            .child(html!("div", {
+                .visible_signal(this.displayed.signal())
+                .child_signal(svg_graph.implications.signal_cloned().map(
+                    |implications| {
+                    Some(Self::div(&implications))
+                }))
+            }))
+        })
+    }
+}
@@ -8,9 +8,12 @@
 
 //! WASM app to show causal information
 
+mod adjustment_section;
 mod app;
 mod bounds;
 mod css;
+mod history;
+mod implications_section;
 mod model_data_section;
 mod section_header;
 mod svgedge;
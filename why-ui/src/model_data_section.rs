@@ -1,12 +1,20 @@
 use crate::section_header::SectionHeader;
-use crate::{css::TEXTAREA_CLASS, svggraph::SvgGraph};
-use dominator::{html, Dom};
+use crate::{
+    css::{ERROR_CLASS, TEXTAREA_CLASS},
+    svggraph::SvgGraph,
+};
+use dominator::{clone, events, html, with_node, Dom};
 use futures_signals::signal::{Mutable, SignalExt};
 use std::sync::Arc;
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::HtmlTextAreaElement;
+
+const PARSE_DELAY_MS: i32 = 500;
 
 pub struct ModelDataSection {
     header: Arc<SectionHeader>,
     displayed: Mutable<bool>,
+    edits: Mutable<u32>,
 }
 
 impl ModelDataSection {
@@ -14,22 +22,52 @@ impl ModelDataSection {
         Arc::new(Self {
             header: SectionHeader::new(" Model code".to_string(), "model_data".to_string()),
             displayed: Mutable::new(true),
+            edits: Mutable::new(0),
         })
     }
 
+    fn schedule_parse(this: &Arc<Self>, svg_graph: &Arc<SvgGraph>) {
+        let edit = {
+            let mut edits = this.edits.lock_mut();
+            *edits = edits.wrapping_add(1);
+            *edits
+        };
+        let parse = Closure::once_into_js(clone!(this, svg_graph => move || {
+            if this.edits.get() == edit {
+                svg_graph.parse_model_data();
+            }
+        }));
+        if let Some(window) = web_sys::window() {
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+                parse.unchecked_ref(),
+                PARSE_DELAY_MS,
+            );
+        }
+    }
+
     pub fn render(this: &Arc<Self>, svg_graph: &Arc<SvgGraph>) -> Dom {
         html!("section", {
             .child(SectionHeader::render(&this.header, &this.displayed))
             .child(html!("div", {
                 .visible_signal(this.displayed.signal())
                 .child(html!("form", {
-                  .child_signal(svg_graph.model_data.signal_cloned().map(
-                    |data| {
-                        Some(html!("textarea", {
-                            .class(&*TEXTAREA_CLASS)
-                            .attr("rows", "10")
-                            .attr("cols", "35")
-                            .text(&data)
+                    .child(html!("textarea" => HtmlTextAreaElement, {
+                        .class(&*TEXTAREA_CLASS)
+                        .attr("rows", "10")
+                        .attr("cols", "35")
+                        .attr("spellcheck", "false")
+                        .prop_signal("value", svg_graph.model_data.signal_cloned())
+                        .with_node!(element => {
+                            .event(clone!(this, svg_graph => move |_: events::Input| {
+                                svg_graph.model_data.set_neq(element.value());
+                                Self::schedule_parse(&this, &svg_graph);
+                            }))
+                        })
+                    }))
+                    .child_signal(svg_graph.parse_error.signal_cloned().map(|error| {
+                        error.map(|message| html!("p", {
+                            .class(&*ERROR_CLASS)
+                            .text(&message)
                         }))
                     }))
                 }))
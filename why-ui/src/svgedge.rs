@@ -1,20 +1,24 @@
-use crate::{css::PATH_CLASS, svggraph::SvgGraph};
+use crate::{css::PATH_CLASS, history::Edit, svggraph::SvgGraph};
 use dominator::{clone, events, svg, with_node, Dom};
-use futures_signals::{map_ref, signal::Mutable};
-use std::sync::Arc;
-use wasm_bindgen::JsCast;
-use why_data::graph::dagitty::EdgeType;
-use why_data::graph::CausalGraph;
-use why_data::{
-    graph::{EdgeIndex, NodeIndex},
-    types::Point,
+use futures_signals::{
+    map_ref,
+    signal::{Mutable, Signal},
 };
+use std::sync::Arc;
+use why_data::graph::dagitty::Mark;
+use why_data::svg::{mark_angle, EdgeGeometry, ARROW_PATH, CIRCLE_PATH};
+use why_data::{graph::EdgeIndex, types::Point};
+
+const CSS_EDGE_STROKE_COLOR: &str = "black";
+const CSS_EDGE_BIASING_STROKE_COLOR: &str = "#c0004d";
+const CSS_EDGE_CYCLE_STROKE_COLOR: &str = "#ff7f00";
 
 #[derive(Clone, Debug)]
 pub struct SvgEdge {
     pub(crate) id: EdgeIndex,
     marked: Mutable<bool>,
     dragging: Mutable<bool>,
+    drag_start: Mutable<Option<Point<f64>>>,
 }
 
 impl SvgEdge {
@@ -23,129 +27,45 @@ impl SvgEdge {
             id,
             marked: Mutable::new(false),
             dragging: Mutable::new(false),
+            drag_start: Mutable::new(None),
         })
     }
 
-    fn svg_edge_anchor(
-        svg_graph: &Arc<SvgGraph>,
-        v1: NodeIndex,
-        point_v1: &Point<f64>,
-        point_v2: &Point<f64>,
-        arrow_head: bool,
-    ) -> Point<f64> {
-        let svg_length = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => {
-                let id = g.node_weight(v1).unwrap().vertex_path_id.lock_ref();
-                let path = dominator::get_id(&id)
-                    .dyn_into::<web_sys::SvgPathElement>()
-                    .unwrap();
-
-                path.get_total_length()
-            }
-            _ => unimplemented!(),
-        };
-
-        let dx = point_v2.x() - point_v1.x();
-        let dy = point_v2.y() - point_v1.y();
-        let length = f64::sqrt((dx * dx) + (dy * dy));
-        let length = if length < 0.01 { 0.01 } else { length };
-
-        let svg_point = if dy > 0.0 {
-            match &*svg_graph.graph.lock_ref() {
-                CausalGraph::Dag(g) => {
-                    let id = g.node_weight(v1).unwrap().vertex_path_id.lock_ref();
-
-                    let path = dominator::get_id(&id)
-                        .dyn_into::<web_sys::SvgPathElement>()
-                        .unwrap();
-                    path.get_point_at_length(
-                        (f64::acos(dx / length) / 2.0 / std::f64::consts::PI * svg_length as f64)
-                            as f32,
-                    )
-                }
-                _ => unimplemented!(),
-            }
-        } else {
-            match &*svg_graph.graph.lock_ref() {
-                CausalGraph::Dag(g) => {
-                    let id = g.node_weight(v1).unwrap().vertex_path_id.lock_ref();
-
-                    let path = dominator::get_id(&id)
-                        .dyn_into::<web_sys::SvgPathElement>()
-                        .unwrap();
-                    path.get_point_at_length(
-                        (1.0 - f64::acos(dx / length) / 2.0 / std::f64::consts::PI) as f32
-                            * svg_length,
-                    )
-                }
-                _ => unimplemented!(),
-            }
-        }
-        .ok()
-        .map(|p| Point::new(p.x() as f64, p.y() as f64))
-        .unwrap_or(Point::new(0.0, 0.0));
-
-        let lp = f64::sqrt(svg_point.x() * svg_point.x() + svg_point.y() * svg_point.y());
-
-        let elongate = if arrow_head { 1.0 } else { (lp + 5.0) / lp };
-
-        Point::new(
-            svg_point.x() * elongate + point_v1.x(),
-            svg_point.y() * elongate + point_v1.y(),
-        )
-    }
-
     fn svg_edge_anchors(
         this: &Arc<SvgEdge>,
         svg_graph: &Arc<SvgGraph>,
         point_v1: &Point<f64>,
         point_v2: &Point<f64>,
     ) -> (Point<f64>, Point<f64>) {
-        let edge_info = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.edge_weight(this.id).unwrap().clone(),
-            _ => unimplemented!(),
-        };
+        let edge_info = svg_graph
+            .graph
+            .lock_ref()
+            .edge_weight(this.id)
+            .unwrap()
+            .clone();
 
-        let (v1, v2) = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.edge_endpoints(this.id).unwrap(),
-            _ => unimplemented!(),
-        };
         let edge_type = *edge_info.edge_type.lock_ref();
-        let edge_point = edge_info.layout_pos.get();
-
-        let p2 = edge_point
-            .map(|p| svg_graph.bounds.lock_ref().to_svg_coordinates(&p))
-            .unwrap_or(*point_v2);
-        let arrow_head = edge_type == EdgeType::Undirected || edge_type == EdgeType::Directed;
-        let v1_anchor = SvgEdge::svg_edge_anchor(&svg_graph.clone(), v1, point_v1, &p2, arrow_head);
-
-        let p1 = edge_point
-            .map(|p| svg_graph.bounds.lock_ref().to_svg_coordinates(&p))
-            .unwrap_or(*point_v1);
-        let arrow_head = edge_type == EdgeType::Undirected;
-        let v2_anchor = SvgEdge::svg_edge_anchor(svg_graph, v2, point_v2, &p1, arrow_head);
+        let control = edge_info
+            .layout_pos
+            .get()
+            .map(|p| svg_graph.bounds.lock_ref().to_svg_coordinates(&p));
+        let geometry = EdgeGeometry::new(point_v1, point_v2, control, edge_type.marks());
 
-        (v1_anchor, v2_anchor)
+        (geometry.source, geometry.target)
     }
 
-    fn calculate_arrow(this: &Arc<SvgEdge>, svg_graph: &Arc<SvgGraph>) -> String {
-        let layout_pos = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.edge_weight(this.id).unwrap().layout_pos.get(),
-            _ => unimplemented!(),
-        };
+    fn calculate_arrow(this: &Arc<SvgEdge>, svg_graph: &Arc<SvgGraph>, front: bool) -> String {
+        let layout_pos = svg_graph
+            .graph
+            .lock_ref()
+            .edge_weight(this.id)
+            .unwrap()
+            .layout_pos
+            .get();
 
-        let (v1, v2) = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.edge_endpoints(this.id).unwrap(),
-            _ => unimplemented!(),
-        };
-        let info_v1 = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.node_weight(v1).unwrap().clone(),
-            _ => unimplemented!(),
-        };
-        let info_v2 = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.node_weight(v2).unwrap().clone(),
-            _ => unimplemented!(),
-        };
+        let (v1, v2) = svg_graph.graph.lock_ref().edge_endpoints(this.id).unwrap();
+        let info_v1 = svg_graph.graph.lock_ref().node_weight(v1).unwrap().clone();
+        let info_v2 = svg_graph.graph.lock_ref().node_weight(v2).unwrap().clone();
 
         let point_v1 = svg_graph
             .bounds
@@ -156,29 +76,21 @@ impl SvgEdge {
             .lock_ref()
             .to_svg_coordinates(&info_v2.layout_pos.get());
 
-        let (_anchor_back, anchor_front) =
+        let (anchor_back, anchor_front) =
             SvgEdge::svg_edge_anchors(this, svg_graph, &point_v1, &point_v2);
+        let (anchor_front, point_back) = if front {
+            (anchor_front, point_v1)
+        } else {
+            (anchor_back, point_v2)
+        };
 
         let sxy = if let Some(p) = layout_pos {
             svg_graph.bounds.lock_ref().to_svg_coordinates(&p)
         } else {
-            point_v1
+            point_back
         };
 
-        let mut afront = 360.0
-            * f64::atan((anchor_front.y() - sxy.y()) / (anchor_front.x() - sxy.x()))
-            / 2.0
-            / std::f64::consts::PI;
-        if sxy.x() < anchor_front.x() {
-            afront += 180.0;
-        }
-        if sxy.x() == anchor_front.x() {
-            afront = if anchor_front.y() > sxy.y() {
-                -90.0
-            } else {
-                90.0
-            }
-        };
+        let afront = mark_angle(&anchor_front, &sxy);
 
         format!(
             "translate({}, {}) rotate({})",
@@ -189,23 +101,17 @@ impl SvgEdge {
     }
 
     fn calculate_edge(this: &Arc<SvgEdge>, svg_graph: &Arc<SvgGraph>) -> String {
-        let layout_pos = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.edge_weight(this.id).unwrap().layout_pos.get(),
-            _ => unimplemented!(),
-        };
+        let layout_pos = svg_graph
+            .graph
+            .lock_ref()
+            .edge_weight(this.id)
+            .unwrap()
+            .layout_pos
+            .get();
 
-        let (v1, v2) = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.edge_endpoints(this.id).unwrap(),
-            _ => unimplemented!(),
-        };
-        let info_v1 = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.node_weight(v1).unwrap().clone(),
-            _ => unimplemented!(),
-        };
-        let info_v2 = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.node_weight(v2).unwrap().clone(),
-            _ => unimplemented!(),
-        };
+        let (v1, v2) = svg_graph.graph.lock_ref().edge_endpoints(this.id).unwrap();
+        let info_v1 = svg_graph.graph.lock_ref().node_weight(v1).unwrap().clone();
+        let info_v2 = svg_graph.graph.lock_ref().node_weight(v2).unwrap().clone();
 
         let point_v1 = svg_graph
             .bounds
@@ -242,53 +148,80 @@ impl SvgEdge {
         line_path
     }
 
+    fn stroke_color(
+        this: &Arc<SvgEdge>,
+        biasing_edges: &[EdgeIndex],
+        cycle_edges: &[EdgeIndex],
+    ) -> &'static str {
+        if cycle_edges.contains(&this.id) {
+            CSS_EDGE_CYCLE_STROKE_COLOR
+        } else if biasing_edges.contains(&this.id) {
+            CSS_EDGE_BIASING_STROKE_COLOR
+        } else {
+            CSS_EDGE_STROKE_COLOR
+        }
+    }
+
+    fn stroke_color_signal(
+        this: &Arc<SvgEdge>,
+        svg_graph: &Arc<SvgGraph>,
+    ) -> impl Signal<Item = &'static str> {
+        let this = this.clone();
+        map_ref! {
+            let biasing_edges = svg_graph.biasing_edges.signal_cloned(),
+            let cycle_edges = svg_graph.cycle_edges.signal_cloned() =>
+            SvgEdge::stroke_color(&this, biasing_edges, cycle_edges)
+        }
+    }
+
     pub fn render(this: &Arc<SvgEdge>, svg_graph: &Arc<SvgGraph>) -> Dom {
-        let edge_info = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.edge_weight(this.id).unwrap().clone(),
-            _ => unimplemented!(),
-        };
-        let (v1, v2) = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.edge_endpoints(this.id).unwrap(),
-            _ => unimplemented!(),
-        };
-        let info_v1 = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.node_weight(v1).unwrap().clone(),
-            _ => unimplemented!(),
-        };
-        let info_v2 = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.node_weight(v2).unwrap().clone(),
-            _ => unimplemented!(),
-        };
+        let edge_info = svg_graph
+            .graph
+            .lock_ref()
+            .edge_weight(this.id)
+            .unwrap()
+            .clone();
+        let (v1, v2) = svg_graph.graph.lock_ref().edge_endpoints(this.id).unwrap();
+        let info_v1 = svg_graph.graph.lock_ref().node_weight(v1).unwrap().clone();
+        let info_v2 = svg_graph.graph.lock_ref().node_weight(v2).unwrap().clone();
 
-        let children = vec![
-            svg!("path", {
-                .attr("stroke-width", "1.5")
-                .attr("fill", "none")
-                .attr("stroke", "black")
-                .attr_signal("d", clone!(this, svg_graph => {
-                    map_ref! {
-                    let _v1 = info_v1.layout_pos.signal_cloned(),
-                    let _v2 = info_v2.layout_pos.signal_cloned(),
-                    let _edge = edge_info.layout_pos.signal_cloned() => move {
-                        SvgEdge::calculate_edge(&this, &svg_graph)
-                    }
-                }}))
-            }),
-            svg!("path", {
+        let edge_type = *edge_info.edge_type.lock_ref();
+        let mut children = vec![svg!("path", {
+            .attr("stroke-width", "1.5")
+            .attr("fill", "none")
+            .attr_signal("stroke", SvgEdge::stroke_color_signal(this, svg_graph))
+            .attr_signal("d", clone!(this, svg_graph => {
+                map_ref! {
+                let _v1 = info_v1.layout_pos.signal_cloned(),
+                let _v2 = info_v2.layout_pos.signal_cloned(),
+                let _edge = edge_info.layout_pos.signal_cloned() => move {
+                    SvgEdge::calculate_edge(&this, &svg_graph)
+                }
+            }}))
+        })];
+
+        let (source_mark, target_mark) = edge_type.marks();
+        for (front, mark) in [(true, target_mark), (false, source_mark)] {
+            let mark_path = match mark {
+                Mark::Arrow => ARROW_PATH,
+                Mark::Circle => CIRCLE_PATH,
+                Mark::Tail => continue,
+            };
+            children.push(svg!("path", {
                 .attr("stroke-width", "1.5")
                 .attr("fill", "white")
-                .attr("stroke", "black")
-                .attr("d", "M-1,0L15,5L15,-5Z")
-                .attr_signal("transform", clone!(this, svg_graph => {
+                .attr_signal("stroke", SvgEdge::stroke_color_signal(this, svg_graph))
+                .attr("d", mark_path)
+                .attr_signal("transform", clone!(this, svg_graph, info_v1, info_v2, edge_info => {
                     map_ref! {
                     let _v1 = info_v1.layout_pos.signal_cloned(),
                     let _v2 = info_v2.layout_pos.signal_cloned(),
                     let _edge = edge_info.layout_pos.signal_cloned() => move {
-                        SvgEdge::calculate_arrow(&this, &svg_graph)
+                        SvgEdge::calculate_arrow(&this, &svg_graph, front)
                     }
                 }}))
-            }),
-        ];
+            }));
+        }
 
         svg!("g", {
             .class(&*PATH_CLASS)
@@ -298,9 +231,11 @@ impl SvgEdge {
                 e.prevent_default();
             })
             .with_node!(graph_element => {
-                .event(clone!(this => move |e: events::PointerDown| {
+                .event(clone!(svg_graph, this => move |e: events::PointerDown| {
                     this.marked.set(!this.marked.get());
                     this.dragging.set_neq(true);
+                    let info = svg_graph.graph.lock_ref().edge_weight(this.id).unwrap().clone();
+                    this.drag_start.set(info.layout_pos.get());
                     if graph_element.set_pointer_capture(e.pointer_id()).is_err() {
                         log::error!("Unable to capture pointer id for edge");
                     }
@@ -308,10 +243,7 @@ impl SvgEdge {
             })
             .event(clone!(svg_graph, this => move |e: events::PointerMove| {
                 if this.dragging.get() {
-                    let info = match &*svg_graph.graph.lock_ref() {
-                        CausalGraph::Dag(g) => g.edge_weight(this.id).unwrap().clone(),
-                        _ => unimplemented!(),
-                    };
+                    let info = svg_graph.graph.lock_ref().edge_weight(this.id).unwrap().clone();
                     log::debug!("Edge PointerMove event x:{} y:{}", e.x() , e.y());
                     log::debug!("Edge PointerMove event page_x:{} page_y:{}", e.page_x() , e.page_y());
                     let ptr_x = e.page_x() - svg_graph.container.lock_ref().as_ref().map(|container| container.left()).unwrap_or(0);
@@ -323,8 +255,14 @@ impl SvgEdge {
                                 info.layout_pos.lock_ref().unwrap().y());
                 }
             }))
-            .event(clone!(this => move |_: events::PointerUp| {
-                this.dragging.set_neq(false);
+            .event(clone!(svg_graph, this => move |_: events::PointerUp| {
+                if this.dragging.replace(false) {
+                    let info = svg_graph.graph.lock_ref().edge_weight(this.id).unwrap().clone();
+                    let start = this.drag_start.get();
+                    if info.layout_pos.get() != start {
+                        svg_graph.record(Edit::MoveEdge { edge: info, pos: start });
+                    }
+                }
             }))
         })
     }
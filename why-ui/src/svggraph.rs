@@ -1,15 +1,20 @@
 use std::sync::Arc;
 
-use dominator::{clone, svg, Dom};
+use dominator::{clone, events, svg, with_node, Dom};
 use futures_signals::signal::SignalExt;
 use futures_signals::{
     signal::Mutable,
     signal_vec::{MutableVec, SignalVecExt},
 };
-use why_data::graph::dagitty::{EdgeInfo, NodeInfo};
-use why_data::graph::CausalGraph;
+use web_sys::SvgElement;
+use why_data::graph::adjustment::AdjustmentCheck;
+use why_data::graph::dagitty::{EdgeInfo, EdgeType, NodeInfo, VertexType};
+use why_data::graph::{CausalGraph, EdgeIndex, GraphKind, NodeIndex};
+use why_data::types::Point;
+use why_parser::dagitty::{DagittyParser, DagittyWriter};
 
 use crate::bounds::ContainerCoordinates;
+use crate::history::{EdgeRecord, Edit, History};
 use crate::{
     bounds::{Bounds, VIEWBOX_HEIGHT, VIEWBOX_WIDTH},
     svgedge::SvgEdge,
@@ -38,26 +43,32 @@ pub struct SvgGraph {
     pub(crate) edges: MutableVec<Arc<SvgEdge>>,
     pub(crate) bounds: Mutable<Bounds>,
     pub(crate) model_data: Mutable<String>,
+    pub(crate) history: Mutable<History>,
+    pub(crate) parse_error: Mutable<Option<String>>,
     pub(crate) current_variable: Mutable<Option<Arc<NodeInfo>>>,
+    pub(crate) adjustment: Mutable<Option<AdjustmentCheck>>,
+    pub(crate) implications: Mutable<Vec<String>>,
+    pub(crate) biasing_edges: Mutable<Vec<EdgeIndex>>,
+    pub(crate) cycle_edges: Mutable<Vec<EdgeIndex>>,
 }
 
 impl SvgGraph {
     pub fn new(graph: CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>) -> Arc<Self> {
         let vertexes = MutableVec::new();
-        let g = match &graph {
-            CausalGraph::Dag(g) => g,
-            _ => unimplemented!("Not implemented yet"),
-        };
-        for idx in g.node_indices() {
+        for idx in graph.node_indices() {
             vertexes.lock_mut().push_cloned(SvgVertex::new(idx))
         }
 
         let edges = MutableVec::new();
-        for idx in g.edge_indices() {
+        for idx in graph.edge_indices() {
             edges.lock_mut().push_cloned(SvgEdge::new(idx))
         }
 
         let bounds = Bounds::calculate_bounds(&graph, VIEWBOX_HEIGHT as i32, VIEWBOX_WIDTH as i32);
+        let adjustment = graph.check_adjustment();
+        let implications = Self::implications(&graph);
+        let biasing_edges = graph.biasing_edges();
+        let cycle_edges = graph.cycle_edges();
 
         Arc::new(Self {
             graph: Mutable::new(graph),
@@ -66,12 +77,284 @@ impl SvgGraph {
             edges,
             bounds: Mutable::new(bounds),
             model_data: Mutable::new(DEFAULT_GRAPH.into()),
+            history: Mutable::new(History::default()),
+            parse_error: Mutable::new(None),
             current_variable: Mutable::new(None),
+            adjustment: Mutable::new(adjustment),
+            implications: Mutable::new(implications),
+            biasing_edges: Mutable::new(biasing_edges),
+            cycle_edges: Mutable::new(cycle_edges),
         })
     }
 
+    fn implications(graph: &CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>) -> Vec<String> {
+        graph
+            .minimal_implications()
+            .unwrap_or_default()
+            .iter()
+            .map(|i| graph.independence_to_string(i))
+            .collect()
+    }
+
+    pub fn update_analysis(&self) {
+        let graph = self.graph.lock_ref();
+        self.adjustment.set(graph.check_adjustment());
+        self.implications.set(Self::implications(&graph));
+        self.biasing_edges.set(graph.biasing_edges());
+        self.cycle_edges.set(graph.cycle_edges());
+    }
+
+    fn node_index(&self, node: &Arc<NodeInfo>) -> Option<NodeIndex> {
+        let graph = self.graph.lock_ref();
+        graph
+            .node_indices()
+            .into_iter()
+            .find(|n| Arc::ptr_eq(graph.node_weight(*n).unwrap(), node))
+    }
+
+    fn edge_index(&self, edge: &Arc<EdgeInfo>) -> Option<EdgeIndex> {
+        let graph = self.graph.lock_ref();
+        graph
+            .edge_indices()
+            .into_iter()
+            .find(|e| Arc::ptr_eq(graph.edge_weight(*e).unwrap(), edge))
+    }
+
+    pub(crate) fn insert_vertex(&self, node: Arc<NodeInfo>) -> NodeIndex {
+        let node_index = self.graph.lock_mut().add_node(node);
+        self.vertexes
+            .lock_mut()
+            .push_cloned(SvgVertex::new(node_index));
+        node_index
+    }
+
+    pub(crate) fn delete_vertex(&self, node: &Arc<NodeInfo>) -> Option<Vec<EdgeRecord>> {
+        let node_index = self.node_index(node)?;
+        let node_edges: Vec<Arc<EdgeInfo>> = {
+            let graph = self.graph.lock_ref();
+            graph
+                .edges(node_index)
+                .into_iter()
+                .map(|e| graph.edge_weight(e).unwrap().clone())
+                .collect()
+        };
+        let records = node_edges
+            .iter()
+            .filter_map(|e| self.delete_edge(e))
+            .collect();
+        self.vertexes.lock_mut().retain(|v| node_index != v.id);
+        self.graph.lock_mut().remove_node(node_index);
+        Some(records)
+    }
+
+    pub(crate) fn insert_edge(&self, edge: &EdgeRecord) -> Option<EdgeIndex> {
+        let source = self.node_index(&edge.source)?;
+        let target = self.node_index(&edge.target)?;
+        let edge_index = self
+            .graph
+            .lock_mut()
+            .add_edge(source, target, edge.info.clone());
+        self.edges.lock_mut().push_cloned(SvgEdge::new(edge_index));
+        Some(edge_index)
+    }
+
+    pub(crate) fn delete_edge(&self, edge: &Arc<EdgeInfo>) -> Option<EdgeRecord> {
+        let edge_index = self.edge_index(edge)?;
+        let mut graph = self.graph.lock_mut();
+        let (source, target) = graph.edge_endpoints(edge_index)?;
+        let record = EdgeRecord {
+            source: graph.node_weight(source)?.clone(),
+            target: graph.node_weight(target)?.clone(),
+            info: graph.remove_edge(edge_index)?,
+        };
+        self.edges.lock_mut().retain(|e| e.id != edge_index);
+        Some(record)
+    }
+
+    pub(crate) fn replace_graph(
+        &self,
+        graph: CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>>,
+    ) -> CausalGraph<Arc<NodeInfo>, Arc<EdgeInfo>> {
+        let bounds = self.bounds.get();
+        let vertexes = graph
+            .node_indices()
+            .into_iter()
+            .map(SvgVertex::new)
+            .collect();
+        let edges = graph.edge_indices().into_iter().map(SvgEdge::new).collect();
+        let old = self.graph.replace(graph);
+        self.vertexes.lock_mut().replace_cloned(vertexes);
+        self.edges.lock_mut().replace_cloned(edges);
+        self.bounds.set(Bounds::calculate_bounds(
+            &self.graph.lock_ref(),
+            bounds.height as i32,
+            bounds.width as i32,
+        ));
+        old
+    }
+
+    fn after_edit(&self) {
+        let current = self.current_variable.get_cloned();
+        if let Some(node) = current {
+            let current = self.node_index(&node).map(|_| node);
+            self.current_variable.set(current);
+        }
+        self.update_model_data();
+        self.update_analysis();
+    }
+
+    /// Change the graph so that the change can be undone.
+    pub(crate) fn edit(&self, edit: Edit) {
+        if let Some(undo) = edit.apply(self) {
+            self.history.lock_mut().record(undo);
+        }
+        self.after_edit();
+    }
+
+    /// Remember how to revert a change already made, like a drag.
+    pub(crate) fn record(&self, undo: Edit) {
+        self.history.lock_mut().record(undo);
+        self.update_model_data();
+    }
+
+    pub fn undo(&self) {
+        let edit = self.history.lock_mut().pop_undo();
+        if let Some(redo) = edit.and_then(|edit| edit.apply(self)) {
+            self.history.lock_mut().push_redo(redo);
+            self.after_edit();
+        }
+    }
+
+    pub fn redo(&self) {
+        let edit = self.history.lock_mut().pop_redo();
+        if let Some(undo) = edit.and_then(|edit| edit.apply(self)) {
+            self.history.lock_mut().push_undo(undo);
+            self.after_edit();
+        }
+    }
+
+    pub fn parse_model_data(&self) {
+        let model_data = self.model_data.get_cloned();
+        match DagittyParser::parse_str(&model_data) {
+            Ok(graph) => {
+                self.parse_error.set(None);
+                // Indices of the live graph have holes after removals, so
+                // compare the models as written instead.
+                let current = DagittyWriter::write_str(&self.graph.lock_ref());
+                if DagittyWriter::write_str(&graph) == current {
+                    return;
+                }
+                self.edit(Edit::Model(Box::new(graph)));
+                // Keep the code as typed rather than as written back.
+                self.model_data.set(model_data);
+            }
+            Err(err) => self.parse_error.set(Some(err.to_string())),
+        }
+    }
+
+    pub fn update_model_data(&self) {
+        let model_data = DagittyWriter::write_str(&self.graph.lock_ref());
+        self.model_data.set(model_data);
+    }
+
+    pub fn alert(message: &str) {
+        log::warn!("{}", message);
+        if let Some(window) = web_sys::window() {
+            let _ = window.alert_with_message(message);
+        }
+    }
+
+    pub fn check_node_id(&self, id: &str) -> Result<(), String> {
+        if id.is_empty() {
+            return Err("Variable names can't be empty".to_string());
+        }
+        if !DagittyParser::is_identifier(id) {
+            return Err(format!(
+                "{} is not a valid variable name, quote names with spaces or symbols",
+                id
+            ));
+        }
+        if self.graph.lock_ref().node_by_id(id).is_some() {
+            return Err(format!("Variable {} already exists", id));
+        }
+        Ok(())
+    }
+
+    pub fn add_vertex(&self, id: &str, pos: Point<f64>) -> Result<(), String> {
+        self.check_node_id(id)?;
+        let node = NodeInfo::new(id, pos.x(), pos.y(), VertexType::None);
+        self.edit(Edit::AddVertex {
+            node: Arc::new(node),
+            edges: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Remove the edge between two vertices, or add one from `source` to `target`.
+    pub fn toggle_edge(&self, source: NodeIndex, target: NodeIndex) -> Result<(), String> {
+        let edit = {
+            let graph = self.graph.lock_ref();
+            let existing = graph
+                .find_edge(source, target)
+                .or_else(|| graph.find_edge(target, source));
+            if let Some(edge) = existing {
+                Edit::RemoveEdge(graph.edge_weight(edge).unwrap().clone())
+            } else {
+                if let Some(cycle) = graph.closes_cycle(source, target) {
+                    return Err(format!(
+                        "Edge would close the cycle {}",
+                        graph.cycle_to_string(&cycle)
+                    ));
+                }
+                let edge_type = match graph.kind {
+                    GraphKind::Ungraph(_) => EdgeType::Undirected,
+                    _ => EdgeType::Directed,
+                };
+                Edit::AddEdge(EdgeRecord {
+                    source: graph.node_weight(source).unwrap().clone(),
+                    target: graph.node_weight(target).unwrap().clone(),
+                    info: Arc::new(EdgeInfo::new("", None, edge_type)),
+                })
+            }
+        };
+        self.edit(edit);
+        Ok(())
+    }
+
+    fn canvas_click(this: &Arc<Self>, e: &events::PointerDown) {
+        if SvgVertex::unmark_all(this) {
+            return;
+        }
+        let ptr_x = e.page_x()
+            - this
+                .container
+                .lock_ref()
+                .as_ref()
+                .map(|container| container.left())
+                .unwrap_or(0);
+        let ptr_y = e.page_y()
+            - this
+                .container
+                .lock_ref()
+                .as_ref()
+                .map(|container| container.top())
+                .unwrap_or(0);
+        let pos = this
+            .bounds
+            .lock_ref()
+            .to_graph_coordinates(&Point::new(ptr_x as f64, ptr_y as f64));
+        let id = web_sys::window()
+            .and_then(|window| window.prompt_with_message("Variable name:").ok())
+            .flatten();
+        if let Some(id) = id {
+            if let Err(message) = this.add_vertex(id.trim(), pos) {
+                Self::alert(&message);
+            }
+        }
+    }
+
     pub fn render(this: &Arc<Self>) -> Dom {
-        svg!("svg", {
+        svg!("svg" => SvgElement, {
             .attr("alt", "Causal graph")
             .attr("style", "font-family: Arial, sans-serif" )
             .attr_signal("style", this.bounds.signal().map(
@@ -108,6 +391,13 @@ impl SvgGraph {
                     SvgEdge::render(&edge, &this)
                 })
             ))
+            .with_node!(element => {
+                .event(clone!(this => move |e: events::PointerDown| {
+                    if e.target() == Some(element.clone().into()) {
+                        Self::canvas_click(&this, &e);
+                    }
+                }))
+            })
         })
     }
 }
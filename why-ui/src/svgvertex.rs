@@ -3,17 +3,18 @@ use futures_signals::signal::Mutable;
 use std::sync::Arc;
 use web_sys::{SvgGraphicsElement, SvgPathElement};
 use why_data::{
-    graph::{dagitty::VertexType, CausalGraph, NodeIndex},
+    graph::{dagitty::VertexType, NodeIndex},
     types::Point,
 };
 
-use crate::svggraph::SvgGraph;
+use crate::{history::Edit, svggraph::SvgGraph};
 
 #[derive(Clone, Debug)]
 pub struct SvgVertex {
     pub(crate) id: NodeIndex,
     marked: Mutable<bool>,
     dragging: Mutable<bool>,
+    drag_start: Mutable<Point<f64>>,
 }
 
 const CSS_VERTEX_TYPE_NONE_FILL_COLOR: &str = "#aaaaaa";
@@ -36,18 +37,42 @@ impl SvgVertex {
             id,
             marked: Mutable::new(false),
             dragging: Mutable::new(false),
+            drag_start: Mutable::new(Point::new(0.0, 0.0)),
         })
     }
 
+    /// Unmark the marked vertex, returning whether there was one.
+    pub fn unmark_all(svg_graph: &Arc<SvgGraph>) -> bool {
+        let mut unmarked = false;
+        for vertex in svg_graph.vertexes.lock_ref().iter() {
+            unmarked |= vertex.marked.replace(false);
+        }
+        if unmarked {
+            svg_graph.current_variable.set(None);
+        }
+        unmarked
+    }
+
+    fn marked_vertex(svg_graph: &Arc<SvgGraph>) -> Option<Arc<SvgVertex>> {
+        svg_graph
+            .vertexes
+            .lock_ref()
+            .iter()
+            .find(|v| v.marked.get())
+            .cloned()
+    }
+
     pub fn render(this: &Arc<Self>, svg_graph: &Arc<SvgGraph>) -> Dom {
-        let info = match &*svg_graph.graph.lock_ref() {
-            CausalGraph::Dag(g) => g.node_weight(this.id).unwrap().clone(),
-            _ => unimplemented!(),
-        };
+        let info = svg_graph
+            .graph
+            .lock_ref()
+            .node_weight(this.id)
+            .unwrap()
+            .clone();
 
         let children = vec![
             svg!("path" => SvgPathElement, {
-                .attr("id", &*info.vertex_path_id.lock_ref())
+                .attr_signal("id", info.vertex_path_id.signal_cloned())
                 .attr("fill-opacity", "0.7")
                 .attr("z-index", "1")
                 .attr_signal("stroke-width", this.marked.signal_ref({|marked|
@@ -85,7 +110,7 @@ impl SvgVertex {
             svg!("text", {
                 .attr("text-anchor", "middle")
                 .attr("y", "35")
-                .text(&info.id)
+                .text_signal(info.id.signal_cloned())
             }),
         ];
 
@@ -102,6 +127,13 @@ impl SvgVertex {
             })
             .with_node!(graph_element => {
                 .event(clone!(this, svg_graph => move |e: events::PointerDown| {
+                    if let Some(source) = Self::marked_vertex(&svg_graph).filter(|v| v.id != this.id) {
+                        Self::unmark_all(&svg_graph);
+                        if let Err(message) = svg_graph.toggle_edge(source.id, this.id) {
+                            SvgGraph::alert(&message);
+                        }
+                        return;
+                    }
                     this.marked.set(!this.marked.get());
                     if this.marked.get() {
                         svg_graph.vertexes.lock_mut().iter().filter(|v| v.id != this.id).for_each(|v| v.marked.set_neq(false));
@@ -110,6 +142,7 @@ impl SvgVertex {
                         svg_graph.current_variable.set(None);
                     }
                     this.dragging.set_neq(true);
+                    this.drag_start.set(info.layout_pos.get());
                     if graph_element.set_pointer_capture(e.pointer_id()).is_err() {
                         log::error!("Unable to capture pointer id for vertex");
                     }
@@ -117,10 +150,7 @@ impl SvgVertex {
             })
             .event(clone!(svg_graph, this => move |e: events::PointerMove| {
                 if this.dragging.get() {
-                    let info = match &*svg_graph.graph.lock_ref() {
-                        CausalGraph::Dag(g) => g.node_weight(this.id).unwrap().clone(),
-                        _ => unimplemented!(),
-                    };
+                    let info = svg_graph.graph.lock_ref().node_weight(this.id).unwrap().clone();
 
                     log::trace!("Vertex PointerMove event x:{} y:{}", e.x() , e.y());
                     log::trace!("Vertex PointerMove event page_x:{} page_y:{}", e.page_x() , e.page_y());
@@ -135,8 +165,14 @@ impl SvgVertex {
                     log::trace!("Vertex PointerMove after graph_coordinates x:{} y:{}", info.layout_pos.lock_ref().x() , info.layout_pos.lock_ref().y());
                 }
             }))
-            .event(clone!(this => move |_: events::PointerUp| {
-                this.dragging.set_neq(false);
+            .event(clone!(svg_graph, this => move |_: events::PointerUp| {
+                if this.dragging.replace(false) {
+                    let info = svg_graph.graph.lock_ref().node_weight(this.id).unwrap().clone();
+                    let start = this.drag_start.get();
+                    if info.layout_pos.get() != start {
+                        svg_graph.record(Edit::MoveVertex { node: info, pos: start });
+                    }
+                }
             }))
         })
     }
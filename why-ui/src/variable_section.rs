@@ -1,3 +1,4 @@
+use crate::history::Edit;
 use crate::svggraph::SvgGraph;
 use crate::{css::BUTTON_CLASS, section_header::SectionHeader};
 use dominator::{clone, events, html, with_node, Dom, DomBuilder};
@@ -5,7 +6,6 @@ use futures_signals::signal::{Mutable, SignalExt};
 use std::sync::Arc;
 use web_sys::HtmlInputElement;
 use why_data::graph::dagitty::{NodeInfo, VertexType};
-use why_data::graph::CausalGraph;
 
 pub struct VariableSection {
     header: Arc<SectionHeader>,
@@ -25,7 +25,7 @@ impl VariableSection {
             .child(html!("span", {
                 .attr("id", "variable_label")
                 .style("font-weight", "bold")
-                .text(node_info.as_ref().map_or("", |v| &v.id ))
+                .text(&node_info.as_ref().map_or(String::new(), |v| v.id.get_cloned()))
             }))
         })
     }
@@ -49,29 +49,43 @@ impl VariableSection {
         vertex_type: VertexType,
     ) {
         if let Some(ref node) = node_info {
-            *node.vertex_type.lock_mut() = vertex_type;
-            *svg_graph.current_variable.lock_mut() = Some(node.clone());
+            svg_graph.edit(Edit::VertexType {
+                node: node.clone(),
+                vertex_type,
+            });
         }
     }
 
     fn remove_vertex(svg_graph: &Arc<SvgGraph>, node_info: &Option<Arc<NodeInfo>>) {
         if let Some(ref node) = node_info {
-            let node_index = {
-                match &*svg_graph.graph.lock_ref() {
-                    CausalGraph::Dag(g) => g.node_indices().find(|i| g[*i].id == node.id).unwrap(),
-                    _ => unimplemented!(),
-                }
+            svg_graph.edit(Edit::RemoveVertex(node.clone()));
+        }
+    }
+
+    fn rename_vertex(svg_graph: &Arc<SvgGraph>, node_info: &Option<Arc<NodeInfo>>) {
+        if let Some(ref node) = node_info {
+            let current = node.id.get_cloned();
+            let id = web_sys::window()
+                .and_then(|window| {
+                    window
+                        .prompt_with_message_and_default("New variable name:", &current)
+                        .ok()
+                })
+                .flatten();
+            let Some(id) = id.map(|id| id.trim().to_string()) else {
+                return;
             };
-            let node_edges = svg_graph.graph.lock_ref().edges(node_index);
-            svg_graph.vertexes.lock_mut().retain(|v| node_index != v.id);
-            svg_graph
-                .edges
-                .lock_mut()
-                .retain(|v| !node_edges.contains(&v.id));
-            (*svg_graph.graph.lock_mut()).remove_node(node_index);
-            let model_data = svg_graph.graph.lock_ref().to_string();
-            *svg_graph.model_data.lock_mut() = model_data;
-            *svg_graph.current_variable.lock_mut() = None;
+            if id == current {
+                return;
+            }
+            if let Err(message) = svg_graph.check_node_id(&id) {
+                SvgGraph::alert(&message);
+                return;
+            }
+            svg_graph.edit(Edit::Rename {
+                node: node.clone(),
+                id,
+            });
         }
     }
 
@@ -208,6 +222,11 @@ impl VariableSection {
                     .class(&*BUTTON_CLASS)
                     .attr("type", "button")
                     .text("rename")
+                    .with_node!(_element => {
+                        .event(clone!(svg_graph, node_info => move |_: events::Click| {
+                            Self::rename_vertex(&svg_graph, &node_info);
+                        }))
+                    })
                 }))
 
             }))